@@ -1,11 +1,12 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
-use cosmwasm_std::{Binary, Deps, DepsMut, Env, MessageInfo, Reply, Response, StdResult, to_binary};
-use cw2::set_contract_version;
-use crate::{execute, query};
+use cosmwasm_std::{Binary, Deps, DepsMut, Env, MessageInfo, Reply, Response, StdResult, SubMsgResult, to_json_binary};
+use cw2::{get_contract_version, set_contract_version};
+use semver::Version;
+use crate::{execute, migrations, query, reply};
 use crate::error::ContractError;
-use crate::msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg};
-use crate::state::{DENOM, ENERGY_TRANSFER_COUNT, ENERGY_TRANSFER_OFFER_COUNT};
+use crate::msg::{Config, ContractStatus, ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg};
+use crate::state::{CONFIG, CONTRACT_STATUS, ENERGY_TRANSFER_COUNT, ENERGY_TRANSFER_OFFER_COUNT};
 
 const CONTRACT_NAME: &str = "crates.io:ev";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -18,13 +19,49 @@ pub fn instantiate(
     msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
-    DENOM.save(deps.storage, &msg.denom)?;
+
+    let admin = match msg.admin {
+        Some(admin) => deps.api.addr_validate(&admin)?,
+        None => info.sender.clone(),
+    };
+    let treasury = msg.treasury
+        .map(|treasury| deps.api.addr_validate(&treasury))
+        .transpose()?;
+    let fee_bps = msg.fee_bps.unwrap_or_default();
+    execute::validate_fee(fee_bps, &treasury)?;
+    let start_timeout = msg.start_timeout.unwrap_or(execute::DEFAULT_START_TIMEOUT);
+    execute::validate_start_timeout(start_timeout)?;
+    let oracle = msg.oracle
+        .map(|oracle| deps.api.addr_validate(&oracle))
+        .transpose()?;
+    let max_price_age = msg.max_price_age.unwrap_or(execute::DEFAULT_MAX_PRICE_AGE);
+    execute::validate_max_price_age(max_price_age)?;
+    let limits = msg.limits.unwrap_or_default();
+    execute::validate_limits(&limits)?;
+
+    let config = Config {
+        admin: admin.clone(),
+        denom: msg.denom,
+        fee_bps,
+        treasury,
+        limits,
+        start_timeout,
+        timeout_policy: msg.timeout_policy.unwrap_or_default(),
+        payout_mode: msg.payout_mode.unwrap_or_default(),
+        accepted_denoms: msg.accepted_denoms.unwrap_or_default(),
+        cw20_tokens: execute::validate_cw20_tokens(deps.as_ref(), msg.cw20_tokens.unwrap_or_default())?,
+        oracle,
+        max_price_age,
+    };
+    CONFIG.save(deps.storage, &config)?;
+    CONTRACT_STATUS.save(deps.storage, &ContractStatus::default())?;
     ENERGY_TRANSFER_OFFER_COUNT.save(deps.storage, &0u64)?;
     ENERGY_TRANSFER_COUNT.save(deps.storage, &0u64)?;
 
     Ok(Response::new()
         .add_attribute("method", "instantiate")
-        .add_attribute("owner", info.sender))
+        .add_attribute("owner", info.sender)
+        .add_attribute("admin", admin))
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -41,14 +78,51 @@ pub fn execute(
             tariff,
             name,
             plug_type,
-        } => execute::publish_energy_transfer_offer(deps, env, info, charger_id, location, tariff, name, plug_type),
+            max_session_duration,
+            overconsumption_policy,
+            prices,
+            fiat_tariff,
+        } => execute::publish_energy_transfer_offer(deps, env, info, charger_id, location, tariff, name, plug_type, max_session_duration, overconsumption_policy, prices, fiat_tariff),
         ExecuteMsg::RemoveEnergyOffer {
             energy_offer_id
         } => execute::remove_energy_offer(deps, env, info, energy_offer_id),
+        ExecuteMsg::UpdateEnergyTransferOffer {
+            energy_transfer_offer_id,
+            tariff,
+            name,
+            location,
+            plug_type,
+            max_session_duration,
+            overconsumption_policy,
+            prices,
+            fiat_tariff,
+            clear_fiat_tariff,
+        } => execute::update_energy_transfer_offer(
+            deps,
+            env,
+            info,
+            energy_transfer_offer_id,
+            tariff,
+            name,
+            location,
+            plug_type,
+            max_session_duration,
+            overconsumption_policy,
+            prices,
+            fiat_tariff,
+            clear_fiat_tariff,
+        ),
+        ExecuteMsg::SetChargerStatus {
+            energy_transfer_offer_id,
+            status,
+        } => execute::set_charger_status(deps, info, energy_transfer_offer_id, status),
         ExecuteMsg::StartEnergyTransfer {
             driver,
             energy_transfer_offer_id,
             energy_to_transfer,
+            from_deposit,
+            denom,
+            max_tariff,
         } => execute::start_energy_transfer(
             deps,
             info,
@@ -56,44 +130,110 @@ pub fn execute(
             env,
             energy_transfer_offer_id,
             energy_to_transfer,
+            from_deposit,
+            denom,
+            max_tariff,
         ),
         ExecuteMsg::EnergyTransferStarted {
             energy_transfer_id
-        } => execute::energy_transfer_started(deps, info, energy_transfer_id),
+        } => execute::energy_transfer_started(deps, env, info, energy_transfer_id),
+        ExecuteMsg::ExtendEnergyTransfer { energy_transfer_id, additional_energy } => {
+            execute::extend_energy_transfer(deps, info, energy_transfer_id, additional_energy)
+        },
         ExecuteMsg::EnergyTransferCompleted {
             energy_transfer_id,
             used_service_units,
         } => execute::energy_transfer_completed(deps, env, info, energy_transfer_id, used_service_units),
+        ExecuteMsg::ReportEnergyProgress { energy_transfer_id, energy_transferred, timestamp } => {
+            execute::report_energy_progress(deps, env, info, energy_transfer_id, energy_transferred, timestamp)
+        },
         ExecuteMsg::CancelEnergyTransfer { energy_transfer_id } => {
             execute::cancel_energy_transfer(deps, env, info, energy_transfer_id)
         },
+        ExecuteMsg::ExpireEnergyTransfer { energy_transfer_id } => {
+            execute::expire_energy_transfer(deps, env, energy_transfer_id)
+        },
+        ExecuteMsg::ClaimTimedOutEnergyTransfer { energy_transfer_id } => {
+            execute::claim_timed_out_energy_transfer(deps, env, info, energy_transfer_id)
+        },
         ExecuteMsg::RemoveEnergyTransfer { energy_transfer_id } => {
             execute::remove_energy_transfer(deps, env, info, energy_transfer_id)
-        }
+        },
+        ExecuteMsg::AddConnector { address, owner } => {
+            execute::add_connector(deps, info, address, owner)
+        },
+        ExecuteMsg::RemoveConnector { address } => {
+            execute::remove_connector(deps, info, address)
+        },
+        ExecuteMsg::UpdateConfig {
+            fee_bps,
+            treasury,
+            limits,
+            start_timeout,
+            timeout_policy,
+            payout_mode,
+            accepted_denoms,
+            cw20_tokens,
+            oracle,
+            max_price_age,
+        } => {
+            execute::update_config(deps, info, fee_bps, treasury, limits, start_timeout, timeout_policy, payout_mode, accepted_denoms, cw20_tokens, oracle, max_price_age)
+        },
+        ExecuteMsg::ProposeAdmin { new_admin } => {
+            execute::propose_admin(deps, info, new_admin)
+        },
+        ExecuteMsg::AcceptAdmin {} => execute::accept_admin(deps, info),
+        ExecuteMsg::SetContractStatus { paused, payouts_frozen } => {
+            execute::set_contract_status(deps, info, paused, payouts_frozen)
+        },
+        ExecuteMsg::ClaimPayouts { asset } => execute::claim_payouts(deps, info, asset),
+        ExecuteMsg::Withdraw { amount, recipient, asset } => execute::withdraw(deps, info, amount, recipient, asset),
+        ExecuteMsg::Deposit {} => execute::deposit(deps, info),
+        ExecuteMsg::WithdrawDeposit { amount } => execute::withdraw_deposit(deps, info, amount),
+        ExecuteMsg::ApproveDepositConnector { connector } => execute::approve_deposit_connector(deps, info, connector),
+        ExecuteMsg::RevokeDepositConnector { connector } => execute::revoke_deposit_connector(deps, info, connector),
+        ExecuteMsg::Receive(msg) => execute::receive_cw20(deps, env, info, msg),
+        ExecuteMsg::PayDebt { energy_transfer_id } => execute::pay_debt(deps, info, energy_transfer_id),
     }
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
-        QueryMsg::AllEnergyTransferOffers {} => to_binary(&query::query_all_energy_transfer_offers(deps)?),
-        QueryMsg::EnergyTransfer { id } => to_binary(&query::query_energy_transfer(deps, id)?),
-        QueryMsg::AllEnergyTransfers {} => to_binary(&query::query_all_energy_transfers(deps)?),
-        QueryMsg::EnergyTransferOffers { owner } => to_binary(&query::query_energy_transfer_offers(deps, owner)?),
-        QueryMsg::OwnEnergyTransfers { driver, transfer_status } => to_binary(&query::query_own_energy_transfers(deps, driver, transfer_status)?),
-        QueryMsg::EnergyTransfers { owner } => to_binary(&query::query_energy_transfers(deps, owner)?),
-        QueryMsg::Denom {} => to_binary(&query::query_denom(deps)?),
-        QueryMsg::EnergyTransferOffer { id } => to_binary(&query::query_energy_transfer_offer(deps, id)?),
+        QueryMsg::AllEnergyTransferOffers { start_after, limit } => to_json_binary(&query::query_all_energy_transfer_offers(deps, start_after, limit)?),
+        QueryMsg::EnergyTransfer { id } => to_json_binary(&query::query_energy_transfer(deps, id)?),
+        QueryMsg::EnergyTransferProgress { id } => to_json_binary(&query::query_energy_transfer_progress(deps, id)?),
+        QueryMsg::AllEnergyTransfers { start_after, limit } => to_json_binary(&query::query_all_energy_transfers(deps, start_after, limit)?),
+        QueryMsg::EnergyTransferOffers { owner, start_after, limit } => to_json_binary(&query::query_energy_transfer_offers(deps, owner, start_after, limit)?),
+        QueryMsg::OwnEnergyTransfers { driver, transfer_status, start_after, limit } => to_json_binary(&query::query_own_energy_transfers(deps, driver, transfer_status, start_after, limit)?),
+        QueryMsg::EnergyTransfers { owner, start_after, limit } => to_json_binary(&query::query_energy_transfers(deps, owner, start_after, limit)?),
+        QueryMsg::PayerEnergyTransfers { payer, start_after, limit } => to_json_binary(&query::query_payer_energy_transfers(deps, payer, start_after, limit)?),
+        QueryMsg::ChargerEnergyTransferOffers { charger_id, start_after, limit } => to_json_binary(&query::query_charger_energy_transfer_offers(deps, charger_id, start_after, limit)?),
+        QueryMsg::ChargerEnergyTransfers { charger_id, start_after, limit } => to_json_binary(&query::query_charger_energy_transfers(deps, charger_id, start_after, limit)?),
+        QueryMsg::EnergyTransfersByStatus { transfer_status, start_after, limit } => to_json_binary(&query::query_energy_transfers_by_status(deps, transfer_status, start_after, limit)?),
+        QueryMsg::Denom {} => to_json_binary(&query::query_denom(deps)?),
+        QueryMsg::EnergyTransferOffer { id } => to_json_binary(&query::query_energy_transfer_offer(deps, id)?),
+        QueryMsg::Connectors { start_after, limit } => to_json_binary(&query::query_connectors(deps, start_after, limit)?),
+        QueryMsg::Config {} => to_json_binary(&query::query_config(deps)?),
+        QueryMsg::ContractStatus {} => to_json_binary(&query::query_contract_status(deps)?),
+        QueryMsg::Claimable { address, asset } => to_json_binary(&query::query_claimable(deps, address, asset)?),
+        QueryMsg::FeeInfo { asset } => to_json_binary(&query::query_fee_info(deps, asset)?),
+        QueryMsg::Balance { address, asset } => to_json_binary(&query::query_balance(deps, address, asset)?),
+        QueryMsg::Deposit { address } => to_json_binary(&query::query_deposit(deps, address)?),
+        QueryMsg::DepositConnectors { address, start_after, limit } => to_json_binary(&query::query_deposit_connectors(deps, address, start_after, limit)?),
+        QueryMsg::Debts { driver, start_after, limit } => to_json_binary(&query::query_debts(deps, driver, start_after, limit)?),
     }
 }
 
 /// Handling submessage reply.
 /// For more info on submessage and reply, see https://github.com/CosmWasm/cosmwasm/blob/main/SEMANTICS.md#submessages
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn reply(_deps: DepsMut, _env: Env, _msg: Reply) -> Result<Response, ContractError> {
-    // With `Response` type, it is still possible to dispatch message to invoke external logic.
-    // See: https://github.com/CosmWasm/cosmwasm/blob/main/SEMANTICS.md#dispatching-messages
-    todo!()
+pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractError> {
+    // Payouts are dispatched with `reply_always`, their record is dropped either way
+    match msg.result {
+        SubMsgResult::Err(error) => reply::payout_failed(deps, msg.id, error),
+        SubMsgResult::Ok(_) => reply::payout_succeeded(deps, msg.id),
+    }
 }
 
 
@@ -101,29 +241,54 @@ pub fn reply(_deps: DepsMut, _env: Env, _msg: Reply) -> Result<Response, Contrac
 /// To make a contract migratable, you need
 /// - this entry_point implemented
 /// - only contract admin can migrate, so admin has to be set at contract initiation time
-/// Handling contract execution
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn migrate(_deps: DepsMut, _env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
-    match msg {
-        // Find matched incoming message variant and execute them with your custom logic.
-        //
-        // With `Response` type, it is possible to dispatch message to invoke external logic.
-        // See: https://github.com/CosmWasm/cosmwasm/blob/main/SEMANTICS.md#dispatching-messages
+pub fn migrate(mut deps: DepsMut, env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
+    let stored = get_contract_version(deps.storage)?;
+    if stored.contract != CONTRACT_NAME {
+        return Err(ContractError::InvalidMigrationContract(stored.contract));
+    }
+
+    let from_version = parse_version(&stored.version)?;
+    let to_version = parse_version(CONTRACT_VERSION)?;
+    if from_version > to_version {
+        return Err(ContractError::CannotDowngrade(stored.version, CONTRACT_VERSION.to_string()));
+    }
+
+    let applied = migrations::run(deps.branch(), &env, &from_version, &msg)?;
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    let mut response = Response::new()
+        .add_attribute("method", "migrate")
+        .add_attribute("from_version", stored.version)
+        .add_attribute("to_version", CONTRACT_VERSION);
+    if !applied.is_empty() {
+        response = response.add_attribute("applied_steps", applied.join(","));
     }
+
+    Ok(response)
+}
+
+fn parse_version(version: &str) -> Result<Version, ContractError> {
+    Version::parse(version).map_err(|err| ContractError::SemVer(err.to_string()))
 }
 
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use cosmwasm_std::{Addr, coins};
-    use cw_multi_test::{App, BasicApp, ContractWrapper, Executor};
-    use crate::msg::{AllEnergyTransferOffersResponse, AllEnergyTransfersResponse, ChargerStatus, DenomResponse, EnergyTransferOfferResponse, EnergyTransferOffersByOwnerResponse, EnergyTransferResponse, Location, PlugType, TransferStatus};
+    use cosmwasm_std::{Addr, BankMsg, coin, coins, CosmosMsg, Decimal, Empty, ReplyOn, Timestamp, to_json_binary, Uint128};
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+    use cw_multi_test::{App, AppResponse, BankSudo, BasicApp, ContractWrapper, Executor};
+    use cw_utils::PaymentError;
+    use proptest::prelude::*;
+    use crate::state::{DEBTS, PAYOUT_COUNT, PAYOUTS};
+    use crate::msg::{AllEnergyTransferOffersResponse, AllEnergyTransfersResponse, Asset, AssetAmount, BalanceResponse, ChargerStatus, ClaimableResponse, ConfigResponse, Connector, ConnectorsResponse, ContractStatusResponse, Debt, DebtsResponse, DenomResponse, DepositConnectorsResponse, DepositResponse, EnergyTransferOfferResponse, EnergyTransferOffersByChargerResponse, EnergyTransferOffersByOwnerResponse, EnergyTransferProgressResponse, EnergyTransferResponse, EnergyTransfersByChargerResponse, EnergyTransfersByPayerResponse, EnergyTransfersByStatusResponse, FeeInfoResponse, FiatTariff, Limits, Location, OraclePriceResponse, OracleQueryMsg, OverconsumptionPolicy, PayoutMode, PlugType, Price, ReceiveMsg, TimeoutPolicy, TransferStatus};
 
     static DRIVER_ADDRESS: &str = "c4e1n65nctlr97na2h9sjul94ge4y95uhtxwmhn9kx";
     static CONTRACT_CREATOR_ADDRESS: &str = "c4e185qx6dnqry2d3crk24u3h3vtfzkqscvuvympam";
     static CONNECTOR_ADDRESS: &str = "c4e15dwxa9jq7mjv3kpw3qxgx4v7asmh3yqh3zre47";
     static OWNER_ADDRESS: &str = "c4e1lt5npfrl4fnvkxm387d8fc59x3vwugagm4vnzm";
+    static SPONSOR_ADDRESS: &str = "c4e1yh5f4v6uq4yjxkqcy3g2d0q7vkfmh8r5a3lz2n";
 
     fn setup_app() -> (App, Addr) {
         let mut app = App::new(|router, _, storage| {
@@ -148,18 +313,39 @@ mod tests {
                 .unwrap()
         });
 
-        let code = ContractWrapper::new(execute, instantiate, query);
+        let code = ContractWrapper::new(execute, instantiate, query).with_reply(reply);
         let code_id = app.store_code(Box::new(code));
         let addr = app
             .instantiate_contract(
                 code_id,
                 Addr::unchecked(CONTRACT_CREATOR_ADDRESS),
-                &InstantiateMsg { denom: "uc4e".to_string() },
+                &InstantiateMsg {
+                    denom: "uc4e".to_string(),
+                    admin: None,
+                    fee_bps: None,
+                    treasury: None,
+                    limits: None,
+                    start_timeout: None,
+                    timeout_policy: None,
+                    payout_mode: None,
+                    accepted_denoms: None,
+                    cw20_tokens: None,
+                    oracle: None,
+                    max_price_age: None,
+                },
                 &[],
                 "Contract",
                 None,
             )
             .unwrap();
+
+        app.execute_contract(
+            Addr::unchecked(CONTRACT_CREATOR_ADDRESS),
+            addr.clone(),
+            &ExecuteMsg::AddConnector { address: CONNECTOR_ADDRESS.to_string(), owner: None },
+            &[],
+        ).unwrap();
+
         (app, addr)
     }
 
@@ -170,9 +356,13 @@ mod tests {
             &ExecuteMsg::PublishEnergyTransferOffer {
                 charger_id: "charger1".to_string(),
                 location: Location { latitude: "60".to_string(), longitude: "60".to_string() },
-                tariff: 50,
+                tariff: Decimal::from_ratio(50u128, 1u128),
                 name: "offer1".to_string(),
                 plug_type: PlugType::Type1,
+                max_session_duration: None,
+                overconsumption_policy: None,
+                prices: None,
+                fiat_tariff: None,
             },
             &[],
         );
@@ -193,6 +383,9 @@ mod tests {
                 energy_transfer_offer_id,
                 energy_to_transfer,
                 driver,
+                from_deposit: None,
+                denom: None,
+                max_tariff: None,
             },
             &coins(500, "uc4e"),
         );
@@ -275,6 +468,7 @@ mod tests {
         assert_eq!(query_res.energy_transfer.status, TransferStatus::Ongoing);
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn execute_energy_transfer_completed(
         app: &mut App,
         addr: Addr,
@@ -325,7 +519,7 @@ mod tests {
     pub fn query_all_transfers(app: &App, addr: Addr, expected_len: usize) {
         let resp: AllEnergyTransfersResponse = app
             .wrap()
-            .query_wasm_smart(addr, &QueryMsg::AllEnergyTransfers {})
+            .query_wasm_smart(addr, &QueryMsg::AllEnergyTransfers { start_after: None, limit: None })
             .unwrap();
         assert_eq!(resp.energy_transfers.len(), expected_len);
     }
@@ -350,7 +544,7 @@ mod tests {
             .unwrap();
 
         assert_eq!(query_res.energy_transfer.id, 1);
-        assert_eq!(query_res.energy_transfer.offered_tariff, 50);
+        assert_eq!(query_res.energy_transfer.offered_tariff, Decimal::from_ratio(50u128, 1u128));
         assert_eq!(query_res.energy_transfer.energy_to_transfer, 10);
         assert_eq!(query_res.energy_transfer.status, TransferStatus::Requested);
 
@@ -374,7 +568,7 @@ mod tests {
         execute_energy_transfer_started(&mut app, addr.clone(), 1);
 
         let err = app.execute_contract(
-            Addr::unchecked("creator"),
+            Addr::unchecked(CONNECTOR_ADDRESS),
             addr.clone(),
             &ExecuteMsg::EnergyTransferStarted {
                 energy_transfer_id: 1
@@ -388,6 +582,175 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_unauthorized_connector() {
+        let (mut app, addr) = setup_app();
+
+        execute_publish_offer(&mut app, addr.clone());
+
+        execute_start_energy_transfer(
+            &mut app,
+            addr.clone(),
+            DRIVER_ADDRESS.to_string(),
+            1,
+            10,
+        );
+
+        // Unregistered sender cannot report lifecycle events
+        let err = app.execute_contract(
+            Addr::unchecked("creator"),
+            addr.clone(),
+            &ExecuteMsg::EnergyTransferStarted {
+                energy_transfer_id: 1
+            },
+            &[],
+        ).unwrap_err();
+
+        assert_eq!(
+            ContractError::UnauthorizedConnector("creator".to_string()),
+            err.downcast().unwrap()
+        );
+
+        // The driver cannot settle its own session
+        let err = app.execute_contract(
+            Addr::unchecked(DRIVER_ADDRESS),
+            addr.clone(),
+            &ExecuteMsg::EnergyTransferCompleted {
+                energy_transfer_id: 1,
+                used_service_units: 0,
+            },
+            &[],
+        ).unwrap_err();
+
+        assert_eq!(
+            ContractError::UnauthorizedConnector(DRIVER_ADDRESS.to_string()),
+            err.downcast().unwrap()
+        );
+
+        // Connector scoped to another owner is rejected
+        app.execute_contract(
+            Addr::unchecked(CONTRACT_CREATOR_ADDRESS),
+            addr.clone(),
+            &ExecuteMsg::AddConnector {
+                address: "connector2".to_string(),
+                owner: Some("owner2".to_string()),
+            },
+            &[],
+        ).unwrap();
+
+        let err = app.execute_contract(
+            Addr::unchecked("connector2"),
+            addr.clone(),
+            &ExecuteMsg::EnergyTransferStarted {
+                energy_transfer_id: 1
+            },
+            &[],
+        ).unwrap_err();
+
+        assert_eq!(
+            ContractError::UnauthorizedConnector("connector2".to_string()),
+            err.downcast().unwrap()
+        );
+
+        // Connector scoped to the offer owner is accepted
+        app.execute_contract(
+            Addr::unchecked(CONTRACT_CREATOR_ADDRESS),
+            addr.clone(),
+            &ExecuteMsg::AddConnector {
+                address: "connector2".to_string(),
+                owner: Some(OWNER_ADDRESS.to_string()),
+            },
+            &[],
+        ).unwrap();
+
+        let res = app.execute_contract(
+            Addr::unchecked("connector2"),
+            addr.clone(),
+            &ExecuteMsg::EnergyTransferStarted {
+                energy_transfer_id: 1
+            },
+            &[],
+        );
+        assert!(res.is_ok());
+    }
+
+    #[test]
+    fn test_add_and_remove_connector() {
+        let (mut app, addr) = setup_app();
+
+        // Only the admin manages connectors
+        let err = app.execute_contract(
+            Addr::unchecked(OWNER_ADDRESS),
+            addr.clone(),
+            &ExecuteMsg::AddConnector {
+                address: OWNER_ADDRESS.to_string(),
+                owner: None,
+            },
+            &[],
+        ).unwrap_err();
+
+        assert_eq!(
+            ContractError::UnauthorizedAdmin(OWNER_ADDRESS.to_string()),
+            err.downcast().unwrap()
+        );
+
+        let query_res: ConnectorsResponse = app
+            .wrap()
+            .query_wasm_smart(addr.clone(), &QueryMsg::Connectors { start_after: None, limit: None })
+            .unwrap();
+
+        assert_eq!(query_res.connectors, vec![Connector { address: CONNECTOR_ADDRESS.to_string(), owner: None }]);
+
+        let res = app.execute_contract(
+            Addr::unchecked(CONTRACT_CREATOR_ADDRESS),
+            addr.clone(),
+            &ExecuteMsg::RemoveConnector {
+                address: CONNECTOR_ADDRESS.to_string(),
+            },
+            &[],
+        );
+        assert!(res.is_ok());
+
+        let err = app.execute_contract(
+            Addr::unchecked(CONTRACT_CREATOR_ADDRESS),
+            addr.clone(),
+            &ExecuteMsg::RemoveConnector {
+                address: CONNECTOR_ADDRESS.to_string(),
+            },
+            &[],
+        ).unwrap_err();
+
+        assert_eq!(
+            ContractError::ConnectorNotFound(CONNECTOR_ADDRESS.to_string()),
+            err.downcast().unwrap()
+        );
+
+        // Removed connector can no longer report lifecycle events
+        execute_publish_offer(&mut app, addr.clone());
+
+        execute_start_energy_transfer(
+            &mut app,
+            addr.clone(),
+            DRIVER_ADDRESS.to_string(),
+            1,
+            10,
+        );
+
+        let err = app.execute_contract(
+            Addr::unchecked(CONNECTOR_ADDRESS),
+            addr.clone(),
+            &ExecuteMsg::EnergyTransferStarted {
+                energy_transfer_id: 1
+            },
+            &[],
+        ).unwrap_err();
+
+        assert_eq!(
+            ContractError::UnauthorizedConnector(CONNECTOR_ADDRESS.to_string()),
+            err.downcast().unwrap()
+        );
+    }
+
     #[test]
     fn test_valid_energy_transfer_completed() {
         let (mut app, addr) = setup_app();
@@ -546,10 +909,134 @@ mod tests {
     }
 
 
+    #[test]
+    fn test_cancel_energy_transfer_unauthorized() {
+        let (mut app, addr) = setup_app();
+
+        execute_publish_offer(&mut app, addr.clone());
+
+        execute_start_energy_transfer(
+            &mut app,
+            addr.clone(),
+            DRIVER_ADDRESS.to_string(),
+            1,
+            10,
+        );
+
+        app.execute_contract(
+            Addr::unchecked(CONTRACT_CREATOR_ADDRESS),
+            addr.clone(),
+            &ExecuteMsg::AddConnector {
+                address: "connector2".to_string(),
+                owner: Some("owner2".to_string()),
+            },
+            &[],
+        ).unwrap();
+
+        // A stranger and a connector of another owner are both denied
+        for sender in ["stranger", "connector2"] {
+            let err = app.execute_contract(
+                Addr::unchecked(sender),
+                addr.clone(),
+                &ExecuteMsg::CancelEnergyTransfer {
+                    energy_transfer_id: 1,
+                },
+                &[],
+            ).unwrap_err();
+
+            assert_eq!(
+                ContractError::UnauthorizedCanceller(sender.to_string()),
+                err.downcast().unwrap()
+            );
+        }
+
+        // The offer owner and the admin may cancel on the driver's behalf
+        execute_cancel_energy_transfer(
+            &mut app,
+            addr.clone(),
+            OWNER_ADDRESS.to_string(),
+            1,
+            1,
+        );
+
+        execute_start_energy_transfer(
+            &mut app,
+            addr.clone(),
+            DRIVER_ADDRESS.to_string(),
+            1,
+            10,
+        );
+
+        execute_cancel_energy_transfer(
+            &mut app,
+            addr.clone(),
+            CONTRACT_CREATOR_ADDRESS.to_string(),
+            2,
+            1,
+        );
+
+        assert_eq!(
+            app.wrap().query_balance(DRIVER_ADDRESS, "uc4e").unwrap().amount.u128(),
+            10000
+        );
+    }
+
+    #[test]
+    fn test_remove_energy_transfer_unauthorized() {
+        let (mut app, addr) = setup_app();
+
+        execute_publish_offer(&mut app, addr.clone());
+
+        execute_start_energy_transfer(
+            &mut app,
+            addr.clone(),
+            DRIVER_ADDRESS.to_string(),
+            1,
+            10,
+        );
+
+        execute_cancel_energy_transfer(
+            &mut app,
+            addr.clone(),
+            DRIVER_ADDRESS.to_string(),
+            1,
+            1,
+        );
+
+        // Neither party of the session can delete its history
+        for sender in ["stranger", DRIVER_ADDRESS, OWNER_ADDRESS] {
+            let err = app.execute_contract(
+                Addr::unchecked(sender),
+                addr.clone(),
+                &ExecuteMsg::RemoveEnergyTransfer {
+                    energy_transfer_id: 1,
+                },
+                &[],
+            ).unwrap_err();
+
+            assert_eq!(
+                ContractError::UnauthorizedRemover(sender.to_string()),
+                err.downcast().unwrap()
+            );
+        }
+
+        let res = app.execute_contract(
+            Addr::unchecked(CONTRACT_CREATOR_ADDRESS),
+            addr.clone(),
+            &ExecuteMsg::RemoveEnergyTransfer {
+                energy_transfer_id: 1,
+            },
+            &[],
+        );
+        assert!(res.is_ok());
+
+        query_all_transfers(&app, addr, 0);
+    }
+
     pub fn query_all_offers(app: &BasicApp, addr: Addr, expected_len: usize) {
         let resp: AllEnergyTransferOffersResponse = app
             .wrap()
-            .query_wasm_smart(addr, &QueryMsg::AllEnergyTransferOffers {})
+            .query_wasm_smart(addr, &QueryMsg::AllEnergyTransferOffers { start_after: None, limit: None })
             .unwrap();
 
         assert_eq!(resp.energy_transfer_offers.len(), expected_len);
@@ -569,7 +1056,7 @@ mod tests {
 
         assert_eq!(query_res.energy_transfer_offer.id, 1);
         assert_eq!(query_res.energy_transfer_offer.charger_id, "charger1");
-        assert_eq!(query_res.energy_transfer_offer.tariff, 50);
+        assert_eq!(query_res.energy_transfer_offer.tariff, Decimal::from_ratio(50u128, 1u128));
         assert_eq!(query_res.energy_transfer_offer.name, "offer1");
     }
 
@@ -584,19 +1071,117 @@ mod tests {
     }
 
     #[test]
-    fn test_query_offers_by_owner() {
+    fn test_query_pagination() {
         let (mut app, addr) = setup_app();
 
-        execute_publish_offer(&mut app, addr.clone());
+        for _ in 0..35 {
+            execute_publish_offer(&mut app, addr.clone());
+        }
+
+        let ids = |resp: AllEnergyTransferOffersResponse| -> Vec<u64> {
+            resp.energy_transfer_offers.iter().map(|offer| offer.id).collect()
+        };
+
+        let resp: AllEnergyTransferOffersResponse = app
+            .wrap()
+            .query_wasm_smart(addr.clone(), &QueryMsg::AllEnergyTransferOffers { start_after: None, limit: None })
+            .unwrap();
+        assert_eq!(ids(resp), (1..=10).collect::<Vec<_>>());
+
+        let resp: AllEnergyTransferOffersResponse = app
+            .wrap()
+            .query_wasm_smart(addr.clone(), &QueryMsg::AllEnergyTransferOffers { start_after: Some(10), limit: Some(3) })
+            .unwrap();
+        assert_eq!(ids(resp), vec![11, 12, 13]);
+
+        // Limit is capped at the maximum page size
+        let resp: AllEnergyTransferOffersResponse = app
+            .wrap()
+            .query_wasm_smart(addr.clone(), &QueryMsg::AllEnergyTransferOffers { start_after: None, limit: Some(100) })
+            .unwrap();
+        assert_eq!(ids(resp), (1..=30).collect::<Vec<_>>());
+
+        let resp: EnergyTransferOffersByOwnerResponse = app
+            .wrap()
+            .query_wasm_smart(addr.clone(), &QueryMsg::EnergyTransferOffers {
+                owner: OWNER_ADDRESS.to_string(),
+                start_after: Some(33),
+                limit: Some(5),
+            })
+            .unwrap();
+        assert_eq!(resp.energy_transfer_offers.iter().map(|offer| offer.id).collect::<Vec<_>>(), vec![34, 35]);
+    }
+
+    #[test]
+    fn test_query_offers_by_owner() {
+        let (mut app, addr) = setup_app();
+
+        execute_publish_offer(&mut app, addr.clone());
 
         let query_res: EnergyTransferOffersByOwnerResponse = app
             .wrap()
-            .query_wasm_smart(addr.clone(), &QueryMsg::EnergyTransferOffers { owner: OWNER_ADDRESS.to_string() })
+            .query_wasm_smart(addr.clone(), &QueryMsg::EnergyTransferOffers { owner: OWNER_ADDRESS.to_string(), start_after: None, limit: None })
             .unwrap();
 
         assert_eq!(query_res.energy_transfer_offers.len(), 1);
     }
 
+    #[test]
+    fn test_query_by_charger_and_status() {
+        let (mut app, addr) = setup_app();
+
+        execute_publish_offer(&mut app, addr.clone());
+        app.execute_contract(
+            Addr::unchecked(OWNER_ADDRESS),
+            addr.clone(),
+            &ExecuteMsg::PublishEnergyTransferOffer {
+                charger_id: "charger2".to_string(),
+                location: Location { latitude: "60".to_string(), longitude: "60".to_string() },
+                tariff: Decimal::from_ratio(50u128, 1u128),
+                name: "offer2".to_string(),
+                plug_type: PlugType::Type1,
+                max_session_duration: None,
+                overconsumption_policy: None,
+                prices: None,
+                fiat_tariff: None,
+            },
+            &[],
+        ).unwrap();
+        execute_start_energy_transfer(&mut app, addr.clone(), DRIVER_ADDRESS.to_string(), 1, 10);
+        execute_cancel_energy_transfer(&mut app, addr.clone(), DRIVER_ADDRESS.to_string(), 1, 1);
+        execute_start_energy_transfer(&mut app, addr.clone(), DRIVER_ADDRESS.to_string(), 1, 10);
+        execute_energy_transfer_started(&mut app, addr.clone(), 2);
+        execute_start_energy_transfer(&mut app, addr.clone(), DRIVER_ADDRESS.to_string(), 2, 10);
+
+        let query_res: EnergyTransferOffersByChargerResponse = app
+            .wrap()
+            .query_wasm_smart(addr.clone(), &QueryMsg::ChargerEnergyTransferOffers { charger_id: "charger2".to_string(), start_after: None, limit: None })
+            .unwrap();
+        assert_eq!(query_res.energy_transfer_offers.iter().map(|offer| offer.id).collect::<Vec<_>>(), vec![2]);
+
+        let query_res: EnergyTransfersByChargerResponse = app
+            .wrap()
+            .query_wasm_smart(addr.clone(), &QueryMsg::ChargerEnergyTransfers { charger_id: "charger1".to_string(), start_after: None, limit: None })
+            .unwrap();
+        assert_eq!(query_res.energy_transfers.iter().map(|transfer| transfer.id).collect::<Vec<_>>(), vec![1, 2]);
+        let query_res: EnergyTransfersByChargerResponse = app
+            .wrap()
+            .query_wasm_smart(addr.clone(), &QueryMsg::ChargerEnergyTransfers { charger_id: "charger1".to_string(), start_after: Some(1), limit: Some(1) })
+            .unwrap();
+        assert_eq!(query_res.energy_transfers.iter().map(|transfer| transfer.id).collect::<Vec<_>>(), vec![2]);
+
+        let query_res: EnergyTransfersByStatusResponse = app
+            .wrap()
+            .query_wasm_smart(addr.clone(), &QueryMsg::EnergyTransfersByStatus { transfer_status: TransferStatus::Requested, start_after: None, limit: None })
+            .unwrap();
+        assert_eq!(query_res.energy_transfers.iter().map(|transfer| transfer.id).collect::<Vec<_>>(), vec![3]);
+        let query_res: EnergyTransfersByStatusResponse = app
+            .wrap()
+            .query_wasm_smart(addr.clone(), &QueryMsg::EnergyTransfersByStatus { transfer_status: TransferStatus::Ongoing, start_after: None, limit: None })
+            .unwrap();
+        assert_eq!(query_res.energy_transfers.iter().map(|transfer| transfer.id).collect::<Vec<_>>(), vec![2]);
+    }
+
     #[test]
     fn test_publish_wrong_energy_offer() {
         let (mut app, addr) = setup_app();
@@ -608,9 +1193,13 @@ mod tests {
             &ExecuteMsg::PublishEnergyTransferOffer {
                 charger_id: "".to_string(),
                 location: Location { latitude: "60".to_string(), longitude: "60".to_string() },
-                tariff: 50,
+                tariff: Decimal::from_ratio(50u128, 1u128),
                 name: "offer2".to_string(),
                 plug_type: PlugType::Type1,
+                max_session_duration: None,
+                overconsumption_policy: None,
+                prices: None,
+                fiat_tariff: None,
             },
             &[],
         ).unwrap_err();
@@ -627,9 +1216,13 @@ mod tests {
             &ExecuteMsg::PublishEnergyTransferOffer {
                 charger_id: "charger2".to_string(),
                 location: Location { latitude: "60".to_string(), longitude: "60".to_string() },
-                tariff: 0,
+                tariff: Decimal::zero(),
                 name: "".to_string(),
                 plug_type: PlugType::Type1,
+                max_session_duration: None,
+                overconsumption_policy: None,
+                prices: None,
+                fiat_tariff: None,
             },
             &[],
         ).unwrap_err();
@@ -697,6 +1290,2641 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_update_config() {
+        let (mut app, addr) = setup_app();
+
+        let err = app.execute_contract(
+            Addr::unchecked(OWNER_ADDRESS),
+            addr.clone(),
+            &ExecuteMsg::UpdateConfig { fee_bps: Some(100), treasury: None, limits: None, start_timeout: None, timeout_policy: None, payout_mode: None, accepted_denoms: None, cw20_tokens: None, oracle: None, max_price_age: None },
+            &[],
+        ).unwrap_err();
+
+        assert_eq!(
+            ContractError::UnauthorizedAdmin(OWNER_ADDRESS.to_string()),
+            err.downcast().unwrap()
+        );
+
+        let err = app.execute_contract(
+            Addr::unchecked(CONTRACT_CREATOR_ADDRESS),
+            addr.clone(),
+            &ExecuteMsg::UpdateConfig { fee_bps: Some(100), treasury: None, limits: None, start_timeout: None, timeout_policy: None, payout_mode: None, accepted_denoms: None, cw20_tokens: None, oracle: None, max_price_age: None },
+            &[],
+        ).unwrap_err();
+
+        assert_eq!(ContractError::MissingTreasury, err.downcast().unwrap());
+
+        let err = app.execute_contract(
+            Addr::unchecked(CONTRACT_CREATOR_ADDRESS),
+            addr.clone(),
+            &ExecuteMsg::UpdateConfig {
+                fee_bps: Some(5000),
+                treasury: Some("treasury".to_string()),
+                limits: None,
+                start_timeout: None,
+                timeout_policy: None,
+                payout_mode: None,
+                accepted_denoms: None,
+                cw20_tokens: None,
+                oracle: None,
+                max_price_age: None,
+            },
+            &[],
+        ).unwrap_err();
+
+        assert_eq!(ContractError::FeeTooHigh(1000, 5000), err.downcast().unwrap());
+
+        // A timeout the start deadline cannot be computed for would block every new session
+        let err = app.execute_contract(
+            Addr::unchecked(CONTRACT_CREATOR_ADDRESS),
+            addr.clone(),
+            &ExecuteMsg::UpdateConfig {
+                fee_bps: None,
+                treasury: None,
+                limits: None,
+                start_timeout: Some(u64::MAX),
+                timeout_policy: None,
+                payout_mode: None,
+                accepted_denoms: None,
+                cw20_tokens: None,
+                oracle: None,
+                max_price_age: None,
+            },
+            &[],
+        ).unwrap_err();
+
+        assert_eq!(ContractError::StartTimeoutLimitExceeded(execute::MAX_START_TIMEOUT, u64::MAX), err.downcast().unwrap());
+
+        let limits = Limits { max_energy_to_transfer: Some(5), max_tariff: Some(Decimal::from_ratio(100u128, 1u128)), max_session_duration: None };
+        let res = app.execute_contract(
+            Addr::unchecked(CONTRACT_CREATOR_ADDRESS),
+            addr.clone(),
+            &ExecuteMsg::UpdateConfig {
+                fee_bps: Some(100),
+                treasury: Some("treasury".to_string()),
+                limits: Some(limits.clone()),
+                start_timeout: Some(600),
+                timeout_policy: None,
+                payout_mode: None,
+                accepted_denoms: None,
+                cw20_tokens: None,
+                oracle: None,
+                max_price_age: None,
+            },
+            &[],
+        );
+        assert!(res.is_ok());
+
+        let query_res: ConfigResponse = app
+            .wrap()
+            .query_wasm_smart(addr.clone(), &QueryMsg::Config {})
+            .unwrap();
+
+        assert_eq!(query_res.config.fee_bps, 100);
+        assert_eq!(query_res.config.treasury, Some(Addr::unchecked("treasury")));
+        assert_eq!(query_res.config.limits, limits);
+        assert_eq!(query_res.config.start_timeout, 600);
+        assert_eq!(query_res.config.denom, "uc4e");
+
+        // Limits are enforced on new offers and transfers
+        let err = app.execute_contract(
+            Addr::unchecked(OWNER_ADDRESS),
+            addr.clone(),
+            &ExecuteMsg::PublishEnergyTransferOffer {
+                charger_id: "charger1".to_string(),
+                location: Location { latitude: "60".to_string(), longitude: "60".to_string() },
+                tariff: Decimal::from_ratio(150u128, 1u128),
+                name: "offer1".to_string(),
+                plug_type: PlugType::Type1,
+                max_session_duration: None,
+                overconsumption_policy: None,
+                prices: None,
+                fiat_tariff: None,
+            },
+            &[],
+        ).unwrap_err();
+
+        assert_eq!(ContractError::TariffLimitExceeded(Decimal::from_ratio(100u128, 1u128), Decimal::from_ratio(150u128, 1u128)), err.downcast().unwrap());
+
+        execute_publish_offer(&mut app, addr.clone());
+
+        let err = app.execute_contract(
+            Addr::unchecked(DRIVER_ADDRESS),
+            addr.clone(),
+            &ExecuteMsg::StartEnergyTransfer {
+                energy_transfer_offer_id: 1,
+                energy_to_transfer: 10,
+                driver: DRIVER_ADDRESS.to_string(),
+                from_deposit: None,
+                denom: None,
+                max_tariff: None,
+            },
+            &coins(500, "uc4e"),
+        ).unwrap_err();
+
+        assert_eq!(ContractError::EnergyLimitExceeded(5, 10), err.downcast().unwrap());
+    }
+
+    #[test]
+    fn test_admin_handover() {
+        let (mut app, addr) = setup_app();
+
+        let err = app.execute_contract(
+            Addr::unchecked(OWNER_ADDRESS),
+            addr.clone(),
+            &ExecuteMsg::AcceptAdmin {},
+            &[],
+        ).unwrap_err();
+
+        assert_eq!(ContractError::NoPendingAdmin, err.downcast().unwrap());
+
+        let err = app.execute_contract(
+            Addr::unchecked(OWNER_ADDRESS),
+            addr.clone(),
+            &ExecuteMsg::ProposeAdmin { new_admin: OWNER_ADDRESS.to_string() },
+            &[],
+        ).unwrap_err();
+
+        assert_eq!(
+            ContractError::UnauthorizedAdmin(OWNER_ADDRESS.to_string()),
+            err.downcast().unwrap()
+        );
+
+        let res = app.execute_contract(
+            Addr::unchecked(CONTRACT_CREATOR_ADDRESS),
+            addr.clone(),
+            &ExecuteMsg::ProposeAdmin { new_admin: OWNER_ADDRESS.to_string() },
+            &[],
+        );
+        assert!(res.is_ok());
+
+        // Admin stays in place until the proposal is accepted
+        let query_res: ConfigResponse = app
+            .wrap()
+            .query_wasm_smart(addr.clone(), &QueryMsg::Config {})
+            .unwrap();
+
+        assert_eq!(query_res.config.admin, Addr::unchecked(CONTRACT_CREATOR_ADDRESS));
+        assert_eq!(query_res.pending_admin, Some(Addr::unchecked(OWNER_ADDRESS)));
+
+        let err = app.execute_contract(
+            Addr::unchecked(DRIVER_ADDRESS),
+            addr.clone(),
+            &ExecuteMsg::AcceptAdmin {},
+            &[],
+        ).unwrap_err();
+
+        assert_eq!(
+            ContractError::UnauthorizedPendingAdmin(DRIVER_ADDRESS.to_string()),
+            err.downcast().unwrap()
+        );
+
+        let res = app.execute_contract(
+            Addr::unchecked(OWNER_ADDRESS),
+            addr.clone(),
+            &ExecuteMsg::AcceptAdmin {},
+            &[],
+        );
+        assert!(res.is_ok());
+
+        let query_res: ConfigResponse = app
+            .wrap()
+            .query_wasm_smart(addr.clone(), &QueryMsg::Config {})
+            .unwrap();
+
+        assert_eq!(query_res.config.admin, Addr::unchecked(OWNER_ADDRESS));
+        assert_eq!(query_res.pending_admin, None);
+
+        // Previous admin lost its rights
+        let err = app.execute_contract(
+            Addr::unchecked(CONTRACT_CREATOR_ADDRESS),
+            addr.clone(),
+            &ExecuteMsg::RemoveConnector { address: CONNECTOR_ADDRESS.to_string() },
+            &[],
+        ).unwrap_err();
+
+        assert_eq!(
+            ContractError::UnauthorizedAdmin(CONTRACT_CREATOR_ADDRESS.to_string()),
+            err.downcast().unwrap()
+        );
+    }
+
+    fn execute_set_contract_status(app: &mut App, addr: Addr, paused: Option<bool>, payouts_frozen: Option<bool>) {
+        let res = app.execute_contract(
+            Addr::unchecked(CONTRACT_CREATOR_ADDRESS),
+            addr,
+            &ExecuteMsg::SetContractStatus { paused, payouts_frozen },
+            &[],
+        ).unwrap();
+
+        assert!(res.events.iter().any(|event| event.ty == "wasm-contract_status_changed"));
+    }
+
+    #[test]
+    fn test_pause_blocks_new_sessions() {
+        let (mut app, addr) = setup_app();
+
+        execute_publish_offer(&mut app, addr.clone());
+        execute_publish_offer(&mut app, addr.clone());
+
+        execute_start_energy_transfer(
+            &mut app,
+            addr.clone(),
+            DRIVER_ADDRESS.to_string(),
+            1,
+            10,
+        );
+
+        let err = app.execute_contract(
+            Addr::unchecked(OWNER_ADDRESS),
+            addr.clone(),
+            &ExecuteMsg::SetContractStatus { paused: Some(true), payouts_frozen: None },
+            &[],
+        ).unwrap_err();
+
+        assert_eq!(
+            ContractError::UnauthorizedAdmin(OWNER_ADDRESS.to_string()),
+            err.downcast().unwrap()
+        );
+
+        execute_set_contract_status(&mut app, addr.clone(), Some(true), None);
+
+        let query_res: ContractStatusResponse = app
+            .wrap()
+            .query_wasm_smart(addr.clone(), &QueryMsg::ContractStatus {})
+            .unwrap();
+
+        assert!(query_res.status.paused);
+        assert!(!query_res.status.payouts_frozen);
+
+        let err = app.execute_contract(
+            Addr::unchecked(OWNER_ADDRESS),
+            addr.clone(),
+            &ExecuteMsg::PublishEnergyTransferOffer {
+                charger_id: "charger1".to_string(),
+                location: Location { latitude: "60".to_string(), longitude: "60".to_string() },
+                tariff: Decimal::from_ratio(50u128, 1u128),
+                name: "offer1".to_string(),
+                plug_type: PlugType::Type1,
+                max_session_duration: None,
+                overconsumption_policy: None,
+                prices: None,
+                fiat_tariff: None,
+            },
+            &[],
+        ).unwrap_err();
+
+        assert_eq!(ContractError::ContractPaused, err.downcast().unwrap());
+
+        let err = app.execute_contract(
+            Addr::unchecked(DRIVER_ADDRESS),
+            addr.clone(),
+            &ExecuteMsg::StartEnergyTransfer {
+                energy_transfer_offer_id: 2,
+                energy_to_transfer: 10,
+                driver: DRIVER_ADDRESS.to_string(),
+                from_deposit: None,
+                denom: None,
+                max_tariff: None,
+            },
+            &coins(500, "uc4e"),
+        ).unwrap_err();
+
+        assert_eq!(ContractError::ContractPaused, err.downcast().unwrap());
+
+        // In-flight session can still be settled
+        execute_energy_transfer_started(&mut app, addr.clone(), 1);
+
+        execute_energy_transfer_completed(
+            &mut app,
+            addr.clone(),
+            1,
+            5,
+            OWNER_ADDRESS.to_string(),
+            DRIVER_ADDRESS.to_string(),
+            10250,
+            9750,
+        );
+
+        execute_set_contract_status(&mut app, addr.clone(), Some(false), None);
+        execute_start_energy_transfer(
+            &mut app,
+            addr.clone(),
+            DRIVER_ADDRESS.to_string(),
+            2,
+            10,
+        );
+    }
+
+    #[test]
+    fn test_frozen_payouts() {
+        let (mut app, addr) = setup_app();
+
+        execute_publish_offer(&mut app, addr.clone());
+
+        execute_start_energy_transfer(
+            &mut app,
+            addr.clone(),
+            DRIVER_ADDRESS.to_string(),
+            1,
+            10,
+        );
+
+        execute_set_contract_status(&mut app, addr.clone(), Some(true), Some(true));
+
+        let err = app.execute_contract(
+            Addr::unchecked(DRIVER_ADDRESS),
+            addr.clone(),
+            &ExecuteMsg::CancelEnergyTransfer { energy_transfer_id: 1 },
+            &[],
+        ).unwrap_err();
+
+        assert_eq!(ContractError::PayoutsFrozen, err.downcast().unwrap());
+
+        let err = app.execute_contract(
+            Addr::unchecked(CONNECTOR_ADDRESS),
+            addr.clone(),
+            &ExecuteMsg::EnergyTransferCompleted { energy_transfer_id: 1, used_service_units: 5 },
+            &[],
+        ).unwrap_err();
+
+        assert_eq!(ContractError::PayoutsFrozen, err.downcast().unwrap());
+
+        // Unfreezing payouts while still paused lets the session be refunded
+        execute_set_contract_status(&mut app, addr.clone(), None, Some(false));
+
+        execute_cancel_energy_transfer(
+            &mut app,
+            addr.clone(),
+            DRIVER_ADDRESS.to_string(),
+            1,
+            1,
+        );
+
+        assert_eq!(
+            app.wrap().query_balance(DRIVER_ADDRESS, "uc4e").unwrap().amount.u128(),
+            10000
+        );
+    }
+
+    #[test]
+    fn test_frozen_payouts_hold_back_refunds() {
+        let (mut app, addr) = setup_app();
+
+        execute_publish_offer(&mut app, addr.clone());
+        execute_set_contract_status(&mut app, addr.clone(), None, Some(true));
+
+        let start = ExecuteMsg::StartEnergyTransfer {
+            energy_transfer_offer_id: 1,
+            energy_to_transfer: 10,
+            driver: DRIVER_ADDRESS.to_string(),
+            from_deposit: None,
+            denom: None,
+            max_tariff: None,
+        };
+        let err = app.execute_contract(Addr::unchecked(DRIVER_ADDRESS), addr.clone(), &start, &coins(600, "uc4e")).unwrap_err();
+        assert_eq!(ContractError::PayoutsFrozen, err.downcast().unwrap());
+
+        // Paying the exact collateral releases nothing
+        app.execute_contract(Addr::unchecked(DRIVER_ADDRESS), addr.clone(), &start, &coins(500, "uc4e")).unwrap();
+        execute_energy_transfer_started(&mut app, addr.clone(), 1);
+
+        let extend = ExecuteMsg::ExtendEnergyTransfer { energy_transfer_id: 1, additional_energy: 2 };
+        let err = app.execute_contract(Addr::unchecked(DRIVER_ADDRESS), addr.clone(), &extend, &coins(150, "uc4e")).unwrap_err();
+        assert_eq!(ContractError::PayoutsFrozen, err.downcast().unwrap());
+        app.execute_contract(Addr::unchecked(DRIVER_ADDRESS), addr.clone(), &extend, &coins(100, "uc4e")).unwrap();
+
+        assert_eq!(app.wrap().query_balance(DRIVER_ADDRESS, "uc4e").unwrap().amount.u128(), 9400);
+    }
+
+    #[test]
+    fn test_migrate_from_v0_1() {
+        let mut deps = mock_dependencies();
+
+        set_contract_version(deps.as_mut().storage, CONTRACT_NAME, "0.1.0").unwrap();
+        migrations::v0_1::DENOM.save(deps.as_mut().storage, &"uc4e".to_string()).unwrap();
+
+        let legacy_offer = migrations::v0_1::EnergyTransferOffer {
+            id: 1,
+            owner: OWNER_ADDRESS.to_string(),
+            charger_id: "charger1".to_string(),
+            charger_status: ChargerStatus::Busy,
+            location: Location { latitude: "60".to_string(), longitude: "60".to_string() },
+            tariff: 50,
+            name: "offer1".to_string(),
+            plug_type: PlugType::Type2,
+        };
+        migrations::v0_1::ENERGY_TRANSFER_OFFERS.save(deps.as_mut().storage, 1, &legacy_offer).unwrap();
+
+        let legacy_transfer = migrations::v0_1::EnergyTransfer {
+            id: 1,
+            energy_transfer_offer_id: 1,
+            charger_id: "charger1".to_string(),
+            owner: OWNER_ADDRESS.to_string(),
+            driver: DRIVER_ADDRESS.to_string(),
+            offered_tariff: 50,
+            status: TransferStatus::Ongoing,
+            collateral: 500,
+            energy_to_transfer: 10,
+            energy_transferred: 0,
+            paid_date: Timestamp::from_seconds(100),
+        };
+        migrations::v0_1::ENERGY_TRANSFERS.save(deps.as_mut().storage, 1, &legacy_transfer).unwrap();
+
+        let err = migrate(deps.as_mut(), mock_env(), MigrateMsg { admin: None }).unwrap_err();
+        assert_eq!(ContractError::MissingMigrationAdmin, err);
+
+        let res = migrate(
+            deps.as_mut(),
+            mock_env(),
+            MigrateMsg { admin: Some(CONTRACT_CREATOR_ADDRESS.to_string()) },
+        ).unwrap();
+        assert!(res.attributes.iter().any(|attr| attr.key == "applied_steps" && attr.value == "0.2.0"));
+
+        let version = get_contract_version(deps.as_ref().storage).unwrap();
+        assert_eq!(version.version, CONTRACT_VERSION);
+
+        let config = query::query_config(deps.as_ref()).unwrap().config;
+        assert_eq!(config.admin, Addr::unchecked(CONTRACT_CREATOR_ADDRESS));
+        assert_eq!(config.denom, "uc4e");
+        assert!(migrations::v0_1::DENOM.may_load(deps.as_ref().storage).unwrap().is_none());
+
+        let offer = query::query_energy_transfer_offer(deps.as_ref(), 1).unwrap().energy_transfer_offer;
+        assert_eq!(offer.charger_status, ChargerStatus::Busy);
+        assert_eq!(offer.tariff, Decimal::from_ratio(50u128, 1u128));
+        assert_eq!(offer.plug_type, PlugType::Type2);
+        assert_eq!(offer.revision, 0);
+        assert_eq!(offer.current_energy_transfer_id, Some(1));
+
+        let transfer = query::query_energy_transfer(deps.as_ref(), 1).unwrap().energy_transfer;
+        assert_eq!(transfer.status, TransferStatus::Ongoing);
+        assert_eq!(transfer.collateral, Uint128::new(500));
+        assert_eq!(transfer.driver, DRIVER_ADDRESS);
+        assert_eq!(transfer.payer, DRIVER_ADDRESS);
+
+        // Indexes are built for the migrated records
+        let offers = query::query_energy_transfer_offers(deps.as_ref(), OWNER_ADDRESS.to_string(), None, None).unwrap();
+        assert_eq!(offers.energy_transfer_offers, vec![offer]);
+        let transfers = query::query_own_energy_transfers(
+            deps.as_ref(),
+            DRIVER_ADDRESS.to_string(),
+            TransferStatus::Ongoing,
+            None,
+            None,
+        ).unwrap();
+        assert_eq!(transfers.energy_transfers, vec![transfer.clone()]);
+        let transfers = query::query_payer_energy_transfers(deps.as_ref(), DRIVER_ADDRESS.to_string(), None, None).unwrap();
+        assert_eq!(transfers.energy_transfers, vec![transfer]);
+
+        // Migrating again to the same version is a no-op
+        let res = migrate(deps.as_mut(), mock_env(), MigrateMsg { admin: None }).unwrap();
+        assert!(!res.attributes.iter().any(|attr| attr.key == "applied_steps"));
+    }
+
+    #[test]
+    fn test_migrate_rejects_downgrade_and_foreign_contract() {
+        let mut deps = mock_dependencies();
+
+        set_contract_version(deps.as_mut().storage, CONTRACT_NAME, "99.0.0").unwrap();
+        let err = migrate(deps.as_mut(), mock_env(), MigrateMsg { admin: None }).unwrap_err();
+        assert_eq!(
+            ContractError::CannotDowngrade("99.0.0".to_string(), CONTRACT_VERSION.to_string()),
+            err
+        );
+
+        set_contract_version(deps.as_mut().storage, "crates.io:cw20-base", "0.1.0").unwrap();
+        let err = migrate(deps.as_mut(), mock_env(), MigrateMsg { admin: None }).unwrap_err();
+        assert_eq!(
+            ContractError::InvalidMigrationContract("crates.io:cw20-base".to_string()),
+            err
+        );
+    }
+
+    #[test]
+    fn test_payouts_dropped_after_settlement() {
+        let (mut app, addr) = setup_app();
+
+        execute_publish_offer(&mut app, addr.clone());
+        execute_start_energy_transfer(&mut app, addr.clone(), DRIVER_ADDRESS.to_string(), 1, 10);
+        execute_energy_transfer_started(&mut app, addr.clone(), 1);
+        execute_energy_transfer_completed(
+            &mut app,
+            addr.clone(),
+            1,
+            5,
+            OWNER_ADDRESS.to_string(),
+            DRIVER_ADDRESS.to_string(),
+            10250,
+            9750,
+        );
+
+        // The refund and the owner's share were both delivered
+        let payout_count = PAYOUT_COUNT.query(&app.wrap(), addr.clone()).unwrap();
+        assert_eq!(payout_count, 2);
+        for reply_id in 1..=payout_count {
+            assert_eq!(PAYOUTS.query(&app.wrap(), addr.clone(), reply_id).unwrap(), None);
+        }
+    }
+
+    #[test]
+    fn test_failed_payout_becomes_claimable() {
+        let mut deps = mock_dependencies();
+
+        instantiate(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(CONTRACT_CREATOR_ADDRESS, &[]),
+            InstantiateMsg {
+                denom: "uc4e".to_string(),
+                admin: None,
+                fee_bps: None,
+                treasury: None,
+                limits: None,
+                start_timeout: None,
+                timeout_policy: None,
+                payout_mode: None,
+                accepted_denoms: None,
+                cw20_tokens: None,
+                oracle: None,
+                max_price_age: None,
+            },
+        ).unwrap();
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(CONTRACT_CREATOR_ADDRESS, &[]),
+            ExecuteMsg::AddConnector { address: CONNECTOR_ADDRESS.to_string(), owner: None },
+        ).unwrap();
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER_ADDRESS, &[]),
+            ExecuteMsg::PublishEnergyTransferOffer {
+                charger_id: "charger1".to_string(),
+                location: Location { latitude: "60".to_string(), longitude: "60".to_string() },
+                tariff: Decimal::from_ratio(50u128, 1u128),
+                name: "offer1".to_string(),
+                plug_type: PlugType::Type1,
+                max_session_duration: None,
+                overconsumption_policy: None,
+                prices: None,
+                fiat_tariff: None,
+            },
+        ).unwrap();
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(DRIVER_ADDRESS, &coins(500, "uc4e")),
+            ExecuteMsg::StartEnergyTransfer {
+                driver: DRIVER_ADDRESS.to_string(),
+                energy_transfer_offer_id: 1,
+                energy_to_transfer: 10,
+                from_deposit: None,
+                denom: None,
+                max_tariff: None,
+            },
+        ).unwrap();
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(CONNECTOR_ADDRESS, &[]),
+            ExecuteMsg::EnergyTransferCompleted { energy_transfer_id: 1, used_service_units: 5 },
+        ).unwrap();
+
+        assert_eq!(res.messages.len(), 2);
+        assert!(res.messages.iter().all(|sub_msg| sub_msg.reply_on == ReplyOn::Always));
+
+        let owner_payout = res.messages
+            .iter()
+            .find(|sub_msg| matches!(
+                &sub_msg.msg,
+                CosmosMsg::Bank(BankMsg::Send { to_address, .. }) if to_address == OWNER_ADDRESS
+            ))
+            .unwrap();
+
+        // The owner refuses the funds, settlement stays in place and the amount becomes claimable
+        reply(
+            deps.as_mut(),
+            mock_env(),
+            Reply { id: owner_payout.id, result: SubMsgResult::Err("blocked address".to_string()) },
+        ).unwrap();
+
+        let claimable = query::query_claimable(deps.as_ref(), OWNER_ADDRESS.to_string(), None).unwrap();
+        assert_eq!(claimable, ClaimableResponse { amount: Uint128::new(250) });
+
+        let transfer = query::query_energy_transfer(deps.as_ref(), 1).unwrap().energy_transfer;
+        assert_eq!(transfer.status, TransferStatus::Paid);
+
+        let err = reply(
+            deps.as_mut(),
+            mock_env(),
+            Reply { id: owner_payout.id, result: SubMsgResult::Err("blocked address".to_string()) },
+        ).unwrap_err();
+        assert_eq!(ContractError::UnknownReplyId(owner_payout.id), err);
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER_ADDRESS, &[]),
+            ExecuteMsg::ClaimPayouts { asset: None },
+        ).unwrap();
+
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send { to_address: OWNER_ADDRESS.to_string(), amount: coins(250, "uc4e") })
+        );
+
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER_ADDRESS, &[]),
+            ExecuteMsg::ClaimPayouts { asset: None },
+        ).unwrap_err();
+        assert_eq!(ContractError::NothingToClaim, err);
+    }
+
+    #[test]
+    fn test_update_energy_transfer_offer() {
+        let (mut app, addr) = setup_app();
+
+        execute_publish_offer(&mut app, addr.clone());
+
+        execute_start_energy_transfer(
+            &mut app,
+            addr.clone(),
+            DRIVER_ADDRESS.to_string(),
+            1,
+            10,
+        );
+
+        let err = app.execute_contract(
+            Addr::unchecked(DRIVER_ADDRESS),
+            addr.clone(),
+            &ExecuteMsg::UpdateEnergyTransferOffer {
+                energy_transfer_offer_id: 1,
+                tariff: Some(Decimal::from_ratio(1u128, 1u128)),
+                name: None,
+                location: None,
+                plug_type: None,
+                max_session_duration: None,
+                overconsumption_policy: None,
+                prices: None,
+                fiat_tariff: None,
+                clear_fiat_tariff: None,
+            },
+            &[],
+        ).unwrap_err();
+
+        assert_eq!(
+            ContractError::InvalidSigner(DRIVER_ADDRESS.to_string()),
+            err.downcast().unwrap()
+        );
+
+        let res = app.execute_contract(
+            Addr::unchecked(OWNER_ADDRESS),
+            addr.clone(),
+            &ExecuteMsg::UpdateEnergyTransferOffer {
+                energy_transfer_offer_id: 1,
+                tariff: Some(Decimal::from_ratio(80u128, 1u128)),
+                name: Some("offer1b".to_string()),
+                location: None,
+                plug_type: Some(PlugType::CCS),
+                max_session_duration: None,
+                overconsumption_policy: None,
+                prices: None,
+                fiat_tariff: None,
+                clear_fiat_tariff: None,
+            },
+            &[],
+        ).unwrap();
+
+        let event = res.events.iter().find(|event| event.ty == "wasm-update_energy_transfer_offer").unwrap();
+        let attribute = |key: &str| event.attributes.iter().find(|attr| attr.key == key).map(|attr| attr.value.clone());
+        assert_eq!(attribute("old_tariff"), Some("50".to_string()));
+        assert_eq!(attribute("new_tariff"), Some("80".to_string()));
+        assert_eq!(attribute("new_plug_type"), Some("CCS".to_string()));
+        assert_eq!(attribute("old_location"), None);
+
+        let query_res: EnergyTransferOfferResponse = app
+            .wrap()
+            .query_wasm_smart(addr.clone(), &QueryMsg::EnergyTransferOffer { id: 1 })
+            .unwrap();
+
+        assert_eq!(query_res.energy_transfer_offer.tariff, Decimal::from_ratio(80u128, 1u128));
+        assert_eq!(query_res.energy_transfer_offer.name, "offer1b");
+        assert_eq!(query_res.energy_transfer_offer.plug_type, PlugType::CCS);
+        assert_eq!(query_res.energy_transfer_offer.revision, 1);
+
+        let err = app.execute_contract(
+            Addr::unchecked(OWNER_ADDRESS),
+            addr.clone(),
+            &ExecuteMsg::UpdateEnergyTransferOffer {
+                energy_transfer_offer_id: 1,
+                tariff: None,
+                name: None,
+                location: None,
+                plug_type: None,
+                max_session_duration: None,
+                overconsumption_policy: None,
+                prices: None,
+                fiat_tariff: None,
+                clear_fiat_tariff: Some(false),
+            },
+            &[],
+        ).unwrap_err();
+        assert_eq!(ContractError::EmptyOfferUpdate(1), err.downcast().unwrap());
+
+        // Resending the current values is not an update either
+        let err = app.execute_contract(
+            Addr::unchecked(OWNER_ADDRESS),
+            addr.clone(),
+            &ExecuteMsg::UpdateEnergyTransferOffer {
+                energy_transfer_offer_id: 1,
+                tariff: Some(Decimal::from_ratio(80u128, 1u128)),
+                name: Some("offer1b".to_string()),
+                location: None,
+                plug_type: Some(PlugType::CCS),
+                max_session_duration: Some(execute::DEFAULT_MAX_SESSION_DURATION),
+                overconsumption_policy: None,
+                prices: Some(vec![]),
+                fiat_tariff: None,
+                clear_fiat_tariff: None,
+            },
+            &[],
+        ).unwrap_err();
+        assert_eq!(ContractError::EmptyOfferUpdate(1), err.downcast().unwrap());
+
+        // Nor is clearing a fiat tariff the offer does not have
+        let err = app.execute_contract(
+            Addr::unchecked(OWNER_ADDRESS),
+            addr.clone(),
+            &ExecuteMsg::UpdateEnergyTransferOffer {
+                energy_transfer_offer_id: 1,
+                tariff: None,
+                name: None,
+                location: None,
+                plug_type: None,
+                max_session_duration: None,
+                overconsumption_policy: None,
+                prices: None,
+                fiat_tariff: None,
+                clear_fiat_tariff: Some(true),
+            },
+            &[],
+        ).unwrap_err();
+        assert_eq!(ContractError::EmptyOfferUpdate(1), err.downcast().unwrap());
+
+        let query_res: EnergyTransferOfferResponse = app
+            .wrap()
+            .query_wasm_smart(addr.clone(), &QueryMsg::EnergyTransferOffer { id: 1 })
+            .unwrap();
+        assert_eq!(query_res.energy_transfer_offer.revision, 1);
+
+        // The session started before the update settles at the old tariff
+        execute_energy_transfer_started(&mut app, addr.clone(), 1);
+
+        execute_energy_transfer_completed(
+            &mut app,
+            addr.clone(),
+            1,
+            5,
+            OWNER_ADDRESS.to_string(),
+            DRIVER_ADDRESS.to_string(),
+            10250,
+            9750,
+        );
+    }
+
+    fn execute_set_charger_status(app: &mut App, addr: Addr, sender: &str, status: ChargerStatus) -> Result<AppResponse, ContractError> {
+        app.execute_contract(
+            Addr::unchecked(sender),
+            addr,
+            &ExecuteMsg::SetChargerStatus { energy_transfer_offer_id: 1, status },
+            &[],
+        ).map_err(|err| err.downcast().unwrap())
+    }
+
+    #[test]
+    fn test_set_charger_status() {
+        let (mut app, addr) = setup_app();
+
+        execute_publish_offer(&mut app, addr.clone());
+
+        let err = execute_set_charger_status(&mut app, addr.clone(), DRIVER_ADDRESS, ChargerStatus::Inactive).unwrap_err();
+        assert_eq!(
+            ContractError::InvalidSigner(DRIVER_ADDRESS.to_string()),
+            err
+        );
+
+        let err = execute_set_charger_status(&mut app, addr.clone(), OWNER_ADDRESS, ChargerStatus::Busy).unwrap_err();
+        assert_eq!(
+            ContractError::InvalidChargerStatusChange(ChargerStatus::Active, ChargerStatus::Busy),
+            err
+        );
+
+        execute_set_charger_status(&mut app, addr.clone(), OWNER_ADDRESS, ChargerStatus::Inactive).unwrap();
+
+        let err = app.execute_contract(
+            Addr::unchecked(DRIVER_ADDRESS),
+            addr.clone(),
+            &ExecuteMsg::StartEnergyTransfer {
+                energy_transfer_offer_id: 1,
+                energy_to_transfer: 10,
+                driver: DRIVER_ADDRESS.to_string(),
+                from_deposit: None,
+                denom: None,
+                max_tariff: None,
+            },
+            &coins(500, "uc4e"),
+        ).unwrap_err();
+        assert_eq!(
+            ContractError::InvalidChargerStatus(ChargerStatus::Active, ChargerStatus::Inactive),
+            err.downcast().unwrap()
+        );
+
+        // The owner's connector can run the charger as well
+        execute_set_charger_status(&mut app, addr.clone(), CONNECTOR_ADDRESS, ChargerStatus::Maintenance).unwrap();
+        execute_set_charger_status(&mut app, addr.clone(), CONNECTOR_ADDRESS, ChargerStatus::Active).unwrap();
+
+        execute_start_energy_transfer(
+            &mut app,
+            addr.clone(),
+            DRIVER_ADDRESS.to_string(),
+            1,
+            10,
+        );
+
+        let err = execute_set_charger_status(&mut app, addr.clone(), OWNER_ADDRESS, ChargerStatus::Inactive).unwrap_err();
+        assert_eq!(
+            ContractError::InvalidChargerStatusChange(ChargerStatus::Busy, ChargerStatus::Inactive),
+            err
+        );
+    }
+
+    #[test]
+    fn test_charger_faults_mid_session() {
+        let (mut app, addr) = setup_app();
+
+        execute_publish_offer(&mut app, addr.clone());
+
+        execute_start_energy_transfer(
+            &mut app,
+            addr.clone(),
+            DRIVER_ADDRESS.to_string(),
+            1,
+            10,
+        );
+        execute_energy_transfer_started(&mut app, addr.clone(), 1);
+
+        execute_set_charger_status(&mut app, addr.clone(), CONNECTOR_ADDRESS, ChargerStatus::Faulted).unwrap();
+
+        // The offer cannot be removed while the session is still open
+        let err = app.execute_contract(
+            Addr::unchecked(OWNER_ADDRESS),
+            addr.clone(),
+            &ExecuteMsg::RemoveEnergyOffer { energy_offer_id: 1 },
+            &[],
+        ).unwrap_err();
+        assert_eq!(ContractError::EnergyTransferInProgress(1), err.downcast().unwrap());
+
+        let err = execute_set_charger_status(&mut app, addr.clone(), OWNER_ADDRESS, ChargerStatus::Active).unwrap_err();
+        assert_eq!(
+            ContractError::InvalidChargerStatusChange(ChargerStatus::Faulted, ChargerStatus::Active),
+            err
+        );
+
+        // The session is settled normally and the charger stays Faulted
+        execute_energy_transfer_completed(
+            &mut app,
+            addr.clone(),
+            1,
+            5,
+            OWNER_ADDRESS.to_string(),
+            DRIVER_ADDRESS.to_string(),
+            10250,
+            9750,
+        );
+
+        let query_res: EnergyTransferOfferResponse = app
+            .wrap()
+            .query_wasm_smart(addr.clone(), &QueryMsg::EnergyTransferOffer { id: 1 })
+            .unwrap();
+        assert_eq!(query_res.energy_transfer_offer.charger_status, ChargerStatus::Faulted);
+        assert_eq!(query_res.energy_transfer_offer.current_energy_transfer_id, None);
+
+        execute_set_charger_status(&mut app, addr.clone(), OWNER_ADDRESS, ChargerStatus::Active).unwrap();
+    }
+
+    #[test]
+    fn test_expire_energy_transfer() {
+        let (mut app, addr) = setup_app();
+
+        execute_publish_offer(&mut app, addr.clone());
+
+        execute_start_energy_transfer(
+            &mut app,
+            addr.clone(),
+            DRIVER_ADDRESS.to_string(),
+            1,
+            10,
+        );
+
+        let query_res: EnergyTransferResponse = app
+            .wrap()
+            .query_wasm_smart(addr.clone(), &QueryMsg::EnergyTransfer { id: 1 })
+            .unwrap();
+        let start_deadline = query_res.energy_transfer.start_deadline;
+        assert_eq!(start_deadline, app.block_info().time.plus_seconds(execute::DEFAULT_START_TIMEOUT));
+
+        // Too early, anyone may send it but only once the deadline has passed
+        let err = app.execute_contract(
+            Addr::unchecked("anyone"),
+            addr.clone(),
+            &ExecuteMsg::ExpireEnergyTransfer { energy_transfer_id: 1 },
+            &[],
+        ).unwrap_err();
+        assert_eq!(ContractError::StartDeadlineNotReached(1, start_deadline), err.downcast().unwrap());
+
+        app.update_block(|block| block.time = block.time.plus_seconds(execute::DEFAULT_START_TIMEOUT + 1));
+
+        app.execute_contract(
+            Addr::unchecked("anyone"),
+            addr.clone(),
+            &ExecuteMsg::ExpireEnergyTransfer { energy_transfer_id: 1 },
+            &[],
+        ).unwrap();
+
+        assert_eq!(
+            app.wrap().query_balance(DRIVER_ADDRESS, "uc4e").unwrap().amount.u128(),
+            10000
+        );
+
+        let query_res: EnergyTransferResponse = app
+            .wrap()
+            .query_wasm_smart(addr.clone(), &QueryMsg::EnergyTransfer { id: 1 })
+            .unwrap();
+        assert_eq!(query_res.energy_transfer.status, TransferStatus::Expired);
+
+        let query_res: EnergyTransferOfferResponse = app
+            .wrap()
+            .query_wasm_smart(addr.clone(), &QueryMsg::EnergyTransferOffer { id: 1 })
+            .unwrap();
+        assert_eq!(query_res.energy_transfer_offer.charger_status, ChargerStatus::Active);
+        assert_eq!(query_res.energy_transfer_offer.current_energy_transfer_id, None);
+
+        // A started session can no longer expire
+        execute_start_energy_transfer(
+            &mut app,
+            addr.clone(),
+            DRIVER_ADDRESS.to_string(),
+            1,
+            10,
+        );
+        execute_energy_transfer_started(&mut app, addr.clone(), 2);
+        app.update_block(|block| block.time = block.time.plus_seconds(execute::DEFAULT_START_TIMEOUT + 1));
+
+        let err = app.execute_contract(
+            Addr::unchecked("anyone"),
+            addr.clone(),
+            &ExecuteMsg::ExpireEnergyTransfer { energy_transfer_id: 2 },
+            &[],
+        ).unwrap_err();
+        assert_eq!(
+            ContractError::InvalidEnergyTransferStatus(TransferStatus::Requested, TransferStatus::Ongoing),
+            err.downcast().unwrap()
+        );
+
+        // Expired sessions can be pruned like settled ones
+        app.execute_contract(
+            Addr::unchecked(CONNECTOR_ADDRESS),
+            addr.clone(),
+            &ExecuteMsg::RemoveEnergyTransfer { energy_transfer_id: 1 },
+            &[],
+        ).unwrap();
+    }
+
+    #[test]
+    fn test_claim_timed_out_energy_transfer() {
+        let (mut app, addr) = setup_app();
+
+        execute_publish_offer(&mut app, addr.clone());
+
+        execute_start_energy_transfer(
+            &mut app,
+            addr.clone(),
+            DRIVER_ADDRESS.to_string(),
+            1,
+            10,
+        );
+        execute_energy_transfer_started(&mut app, addr.clone(), 1);
+
+        let err = app.execute_contract(
+            Addr::unchecked(DRIVER_ADDRESS),
+            addr.clone(),
+            &ExecuteMsg::ClaimTimedOutEnergyTransfer { energy_transfer_id: 1 },
+            &[],
+        ).unwrap_err();
+        assert_eq!(
+            ContractError::SessionNotTimedOut(1, app.block_info().time.plus_seconds(execute::DEFAULT_MAX_SESSION_DURATION)),
+            err.downcast().unwrap()
+        );
+
+        app.update_block(|block| block.time = block.time.plus_seconds(execute::DEFAULT_MAX_SESSION_DURATION + 1));
+
+        // Only the driver can pull the plug on a session
+        let err = app.execute_contract(
+            Addr::unchecked(OWNER_ADDRESS),
+            addr.clone(),
+            &ExecuteMsg::ClaimTimedOutEnergyTransfer { energy_transfer_id: 1 },
+            &[],
+        ).unwrap_err();
+        assert_eq!(ContractError::InvalidSigner(OWNER_ADDRESS.to_string()), err.downcast().unwrap());
+
+        app.execute_contract(
+            Addr::unchecked(DRIVER_ADDRESS),
+            addr.clone(),
+            &ExecuteMsg::ClaimTimedOutEnergyTransfer { energy_transfer_id: 1 },
+            &[],
+        ).unwrap();
+
+        assert_eq!(
+            app.wrap().query_balance(DRIVER_ADDRESS, "uc4e").unwrap().amount.u128(),
+            10000
+        );
+        assert_eq!(
+            app.wrap().query_balance(OWNER_ADDRESS, "uc4e").unwrap().amount.u128(),
+            10000
+        );
+
+        let query_res: EnergyTransferResponse = app
+            .wrap()
+            .query_wasm_smart(addr.clone(), &QueryMsg::EnergyTransfer { id: 1 })
+            .unwrap();
+        assert_eq!(query_res.energy_transfer.status, TransferStatus::TimedOut);
+
+        // The charger is held back for review
+        let query_res: EnergyTransferOfferResponse = app
+            .wrap()
+            .query_wasm_smart(addr.clone(), &QueryMsg::EnergyTransferOffer { id: 1 })
+            .unwrap();
+        assert_eq!(query_res.energy_transfer_offer.charger_status, ChargerStatus::Faulted);
+        assert_eq!(query_res.energy_transfer_offer.current_energy_transfer_id, None);
+
+        // A late completion report is rejected
+        let err = app.execute_contract(
+            Addr::unchecked(CONNECTOR_ADDRESS),
+            addr.clone(),
+            &ExecuteMsg::EnergyTransferCompleted { energy_transfer_id: 1, used_service_units: 10 },
+            &[],
+        ).unwrap_err();
+        assert_eq!(
+            ContractError::InvalidEnergyTransferMultipleStatuses(TransferStatus::Requested, TransferStatus::Ongoing, TransferStatus::TimedOut),
+            err.downcast().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_session_duration_limit() {
+        let (mut app, addr) = setup_app();
+
+        let publish = |max_session_duration: u64| ExecuteMsg::PublishEnergyTransferOffer {
+            charger_id: "charger1".to_string(),
+            location: Location { latitude: "60".to_string(), longitude: "60".to_string() },
+            tariff: Decimal::from_ratio(50u128, 1u128),
+            name: "offer1".to_string(),
+            plug_type: PlugType::Type1,
+            max_session_duration: Some(max_session_duration),
+            overconsumption_policy: None,
+            prices: None,
+            fiat_tariff: None,
+        };
+        let update = |max_session_duration: u64| ExecuteMsg::UpdateEnergyTransferOffer {
+            energy_transfer_offer_id: 1,
+            tariff: None,
+            name: None,
+            location: None,
+            plug_type: None,
+            max_session_duration: Some(max_session_duration),
+            overconsumption_policy: None,
+            prices: None,
+            fiat_tariff: None,
+            clear_fiat_tariff: None,
+        };
+        let set_limit = |max_session_duration: u64| ExecuteMsg::UpdateConfig {
+            fee_bps: None,
+            treasury: None,
+            limits: Some(Limits { max_session_duration: Some(max_session_duration), ..Limits::default() }),
+            start_timeout: None,
+            timeout_policy: None,
+            payout_mode: None,
+            accepted_denoms: None,
+            cw20_tokens: None,
+            oracle: None,
+            max_price_age: None,
+        };
+
+        // A duration the session deadline cannot be computed for is rejected up front
+        let err = app.execute_contract(Addr::unchecked(OWNER_ADDRESS), addr.clone(), &publish(u64::MAX), &[]).unwrap_err();
+        assert_eq!(
+            ContractError::SessionDurationLimitExceeded(execute::MAX_SESSION_DURATION, u64::MAX),
+            err.downcast().unwrap()
+        );
+        app.execute_contract(Addr::unchecked(OWNER_ADDRESS), addr.clone(), &publish(execute::MAX_SESSION_DURATION), &[]).unwrap();
+
+        let err = app.execute_contract(Addr::unchecked(CONTRACT_CREATOR_ADDRESS), addr.clone(), &set_limit(execute::MAX_SESSION_DURATION + 1), &[]).unwrap_err();
+        assert_eq!(
+            ContractError::SessionDurationLimitExceeded(execute::MAX_SESSION_DURATION, execute::MAX_SESSION_DURATION + 1),
+            err.downcast().unwrap()
+        );
+        app.execute_contract(Addr::unchecked(CONTRACT_CREATOR_ADDRESS), addr.clone(), &set_limit(3600), &[]).unwrap();
+
+        let err = app.execute_contract(Addr::unchecked(OWNER_ADDRESS), addr.clone(), &update(7200), &[]).unwrap_err();
+        assert_eq!(ContractError::SessionDurationLimitExceeded(3600, 7200), err.downcast().unwrap());
+        let err = app.execute_contract(Addr::unchecked(OWNER_ADDRESS), addr.clone(), &publish(7200), &[]).unwrap_err();
+        assert_eq!(ContractError::SessionDurationLimitExceeded(3600, 7200), err.downcast().unwrap());
+        app.execute_contract(Addr::unchecked(OWNER_ADDRESS), addr.clone(), &update(3600), &[]).unwrap();
+
+        assert!(execute::checked_plus_seconds(Timestamp::from_seconds(1), u64::MAX).is_err());
+    }
+
+    fn execute_report_energy_progress(app: &mut App, addr: Addr, energy_transfer_id: u64, energy_transferred: u64, timestamp: Timestamp) -> Result<AppResponse, ContractError> {
+        app.execute_contract(
+            Addr::unchecked(CONNECTOR_ADDRESS),
+            addr,
+            &ExecuteMsg::ReportEnergyProgress { energy_transfer_id, energy_transferred, timestamp },
+            &[],
+        ).map_err(|err| err.downcast().unwrap())
+    }
+
+    #[test]
+    fn test_report_energy_progress() {
+        let (mut app, addr) = setup_app();
+
+        execute_publish_offer(&mut app, addr.clone());
+
+        execute_start_energy_transfer(
+            &mut app,
+            addr.clone(),
+            DRIVER_ADDRESS.to_string(),
+            1,
+            10,
+        );
+
+        let started_at = app.block_info().time;
+        let err = execute_report_energy_progress(&mut app, addr.clone(), 1, 1, started_at).unwrap_err();
+        assert_eq!(
+            ContractError::InvalidEnergyTransferStatus(TransferStatus::Ongoing, TransferStatus::Requested),
+            err
+        );
+
+        execute_energy_transfer_started(&mut app, addr.clone(), 1);
+        app.update_block(|block| block.time = block.time.plus_seconds(60));
+        let now = app.block_info().time;
+
+        // Readings must be newer than the session start and not ahead of the chain
+        let err = execute_report_energy_progress(&mut app, addr.clone(), 1, 1, started_at).unwrap_err();
+        assert_eq!(ContractError::ReadingOutOfOrder(started_at, started_at), err);
+        let err = execute_report_energy_progress(&mut app, addr.clone(), 1, 1, now.plus_seconds(1)).unwrap_err();
+        assert_eq!(ContractError::ReadingInFuture(now.plus_seconds(1)), err);
+
+        execute_report_energy_progress(&mut app, addr.clone(), 1, 4, now).unwrap();
+
+        let progress: EnergyTransferProgressResponse = app
+            .wrap()
+            .query_wasm_smart(addr.clone(), &QueryMsg::EnergyTransferProgress { id: 1 })
+            .unwrap();
+        assert_eq!(
+            progress,
+            EnergyTransferProgressResponse {
+                energy_transfer_id: 1,
+                status: TransferStatus::Ongoing,
+                energy_to_transfer: 10,
+                energy_transferred: 4,
+                amount_due: Uint128::new(200),
+                started_at: Some(started_at),
+                last_reported_at: Some(now),
+            }
+        );
+
+        app.update_block(|block| block.time = block.time.plus_seconds(60));
+        let now = app.block_info().time;
+        let err = execute_report_energy_progress(&mut app, addr.clone(), 1, 3, now).unwrap_err();
+        assert_eq!(ContractError::EnergyReadingDecreased(4, 3), err);
+
+        // Reaching the requested energy settles the session
+        execute_report_energy_progress(&mut app, addr.clone(), 1, 10, now).unwrap();
+
+        let progress: EnergyTransferProgressResponse = app
+            .wrap()
+            .query_wasm_smart(addr.clone(), &QueryMsg::EnergyTransferProgress { id: 1 })
+            .unwrap();
+        assert_eq!(progress.status, TransferStatus::Paid);
+        assert_eq!(app.wrap().query_balance(OWNER_ADDRESS, "uc4e").unwrap().amount.u128(), 10500);
+        assert_eq!(app.wrap().query_balance(DRIVER_ADDRESS, "uc4e").unwrap().amount.u128(), 9500);
+
+        let query_res: EnergyTransferOfferResponse = app
+            .wrap()
+            .query_wasm_smart(addr.clone(), &QueryMsg::EnergyTransferOffer { id: 1 })
+            .unwrap();
+        assert_eq!(query_res.energy_transfer_offer.charger_status, ChargerStatus::Active);
+    }
+
+    #[test]
+    fn test_timed_out_energy_transfer_settles_last_reading() {
+        let (mut app, addr) = setup_app();
+
+        app.execute_contract(
+            Addr::unchecked(CONTRACT_CREATOR_ADDRESS),
+            addr.clone(),
+            &ExecuteMsg::UpdateConfig {
+                fee_bps: None,
+                treasury: None,
+                limits: None,
+                start_timeout: None,
+                timeout_policy: Some(TimeoutPolicy::LastMeterReading),
+                payout_mode: None,
+                accepted_denoms: None,
+                cw20_tokens: None,
+                oracle: None,
+                max_price_age: None,
+            },
+            &[],
+        ).unwrap();
+
+        execute_publish_offer(&mut app, addr.clone());
+        execute_start_energy_transfer(
+            &mut app,
+            addr.clone(),
+            DRIVER_ADDRESS.to_string(),
+            1,
+            10,
+        );
+        execute_energy_transfer_started(&mut app, addr.clone(), 1);
+
+        app.update_block(|block| block.time = block.time.plus_seconds(60));
+        let now = app.block_info().time;
+        execute_report_energy_progress(&mut app, addr.clone(), 1, 4, now).unwrap();
+
+        app.update_block(|block| block.time = block.time.plus_seconds(execute::DEFAULT_MAX_SESSION_DURATION));
+        app.execute_contract(
+            Addr::unchecked(DRIVER_ADDRESS),
+            addr.clone(),
+            &ExecuteMsg::ClaimTimedOutEnergyTransfer { energy_transfer_id: 1 },
+            &[],
+        ).unwrap();
+
+        assert_eq!(app.wrap().query_balance(OWNER_ADDRESS, "uc4e").unwrap().amount.u128(), 10200);
+        assert_eq!(app.wrap().query_balance(DRIVER_ADDRESS, "uc4e").unwrap().amount.u128(), 9800);
+    }
+
+    #[test]
+    fn test_extend_energy_transfer() {
+        let (mut app, addr) = setup_app();
+
+        execute_publish_offer(&mut app, addr.clone());
+        execute_start_energy_transfer(
+            &mut app,
+            addr.clone(),
+            DRIVER_ADDRESS.to_string(),
+            1,
+            10,
+        );
+
+        let err = app.execute_contract(
+            Addr::unchecked(DRIVER_ADDRESS),
+            addr.clone(),
+            &ExecuteMsg::ExtendEnergyTransfer { energy_transfer_id: 1, additional_energy: 4 },
+            &coins(200, "uc4e"),
+        ).unwrap_err();
+        assert_eq!(
+            ContractError::InvalidEnergyTransferStatus(TransferStatus::Ongoing, TransferStatus::Requested),
+            err.downcast().unwrap()
+        );
+
+        execute_energy_transfer_started(&mut app, addr.clone(), 1);
+
+        // A tariff raised mid-session does not apply to the top-up
+        app.execute_contract(
+            Addr::unchecked(OWNER_ADDRESS),
+            addr.clone(),
+            &ExecuteMsg::UpdateEnergyTransferOffer {
+                energy_transfer_offer_id: 1,
+                tariff: Some(Decimal::from_ratio(80u128, 1u128)),
+                name: None,
+                location: None,
+                plug_type: None,
+                max_session_duration: None,
+                overconsumption_policy: None,
+                prices: None,
+                fiat_tariff: None,
+                clear_fiat_tariff: None,
+            },
+            &[],
+        ).unwrap();
+
+        let err = app.execute_contract(
+            Addr::unchecked(OWNER_ADDRESS),
+            addr.clone(),
+            &ExecuteMsg::ExtendEnergyTransfer { energy_transfer_id: 1, additional_energy: 4 },
+            &coins(200, "uc4e"),
+        ).unwrap_err();
+        assert_eq!(ContractError::InvalidSigner(OWNER_ADDRESS.to_string()), err.downcast().unwrap());
+
+        let err = app.execute_contract(
+            Addr::unchecked(DRIVER_ADDRESS),
+            addr.clone(),
+            &ExecuteMsg::ExtendEnergyTransfer { energy_transfer_id: 1, additional_energy: 4 },
+            &coins(150, "uc4e"),
+        ).unwrap_err();
+        assert_eq!(
+            ContractError::InvalidFunds { expected: coin(200, "uc4e"), received: coin(150, "uc4e") },
+            err.downcast().unwrap()
+        );
+
+        let res = app.execute_contract(
+            Addr::unchecked(DRIVER_ADDRESS),
+            addr.clone(),
+            &ExecuteMsg::ExtendEnergyTransfer { energy_transfer_id: 1, additional_energy: 4 },
+            &coins(200, "uc4e"),
+        ).unwrap();
+        let event = res.events.iter().find(|event| event.ty == "wasm-extend_energy_transfer").unwrap();
+        let attribute = |key: &str| event.attributes.iter().find(|attr| attr.key == key).map(|attr| attr.value.clone());
+        assert_eq!(attribute("energy_to_transfer"), Some("14".to_string()));
+        assert_eq!(attribute("collateral"), Some("700".to_string()));
+
+        // The whole extended session is settled at the original tariff
+        execute_energy_transfer_completed(
+            &mut app,
+            addr.clone(),
+            1,
+            12,
+            OWNER_ADDRESS.to_string(),
+            DRIVER_ADDRESS.to_string(),
+            10600,
+            9400,
+        );
+    }
+
+    #[test]
+    fn test_overconsumption() {
+        let (mut app, addr) = setup_app();
+
+        // Hard cap, the default policy
+        execute_publish_offer(&mut app, addr.clone());
+        execute_start_energy_transfer(
+            &mut app,
+            addr.clone(),
+            DRIVER_ADDRESS.to_string(),
+            1,
+            10,
+        );
+        execute_energy_transfer_completed(
+            &mut app,
+            addr.clone(),
+            1,
+            12,
+            OWNER_ADDRESS.to_string(),
+            DRIVER_ADDRESS.to_string(),
+            10500,
+            9500,
+        );
+
+        let query_res: EnergyTransferResponse = app
+            .wrap()
+            .query_wasm_smart(addr.clone(), &QueryMsg::EnergyTransfer { id: 1 })
+            .unwrap();
+        assert_eq!(query_res.energy_transfer.energy_transferred, 10);
+        assert_eq!(query_res.energy_transfer.excess_energy, 2);
+
+        // Driver debt
+        app.execute_contract(
+            Addr::unchecked(OWNER_ADDRESS),
+            addr.clone(),
+            &ExecuteMsg::PublishEnergyTransferOffer {
+                charger_id: "charger2".to_string(),
+                location: Location { latitude: "60".to_string(), longitude: "60".to_string() },
+                tariff: Decimal::from_ratio(50u128, 1u128),
+                name: "offer2".to_string(),
+                plug_type: PlugType::Type2,
+                max_session_duration: None,
+                overconsumption_policy: Some(OverconsumptionPolicy::DriverDebt),
+                prices: None,
+                fiat_tariff: None,
+            },
+            &[],
+        ).unwrap();
+        execute_start_energy_transfer(
+            &mut app,
+            addr.clone(),
+            DRIVER_ADDRESS.to_string(),
+            2,
+            10,
+        );
+        let res = app.execute_contract(
+            Addr::unchecked(CONNECTOR_ADDRESS),
+            addr.clone(),
+            &ExecuteMsg::EnergyTransferCompleted { energy_transfer_id: 2, used_service_units: 13 },
+            &[],
+        ).unwrap();
+
+        let event = res.events.iter().find(|event| event.ty == "wasm-energy_overconsumption").unwrap();
+        let attribute = |key: &str| event.attributes.iter().find(|attr| attr.key == key).map(|attr| attr.value.clone());
+        assert_eq!(attribute("excess_energy"), Some("3".to_string()));
+        assert_eq!(attribute("debt"), Some("150".to_string()));
+
+        let debts: DebtsResponse = app
+            .wrap()
+            .query_wasm_smart(addr.clone(), &QueryMsg::Debts { driver: DRIVER_ADDRESS.to_string(), start_after: None, limit: None })
+            .unwrap();
+        assert_eq!(
+            debts,
+            DebtsResponse {
+                debts: vec![Debt {
+                    energy_transfer_id: 2,
+                    driver: DRIVER_ADDRESS.to_string(),
+                    owner: OWNER_ADDRESS.to_string(),
+                    amount: Uint128::new(150),
+                    asset: Asset::Native("uc4e".to_string()),
+                }],
+                total: vec![AssetAmount { asset: Asset::Native("uc4e".to_string()), amount: Uint128::new(150) }],
+            }
+        );
+
+        // No new session until the debt is cleared
+        let err = app.execute_contract(
+            Addr::unchecked(DRIVER_ADDRESS),
+            addr.clone(),
+            &ExecuteMsg::StartEnergyTransfer {
+                driver: DRIVER_ADDRESS.to_string(),
+                energy_transfer_offer_id: 1,
+                energy_to_transfer: 10,
+                from_deposit: None,
+                denom: None,
+                max_tariff: None,
+            },
+            &coins(500, "uc4e"),
+        ).unwrap_err();
+        assert_eq!(ContractError::OutstandingDebt(DRIVER_ADDRESS.to_string()), err.downcast().unwrap());
+
+        let err = app.execute_contract(
+            Addr::unchecked(DRIVER_ADDRESS),
+            addr.clone(),
+            &ExecuteMsg::PayDebt { energy_transfer_id: 2 },
+            &coins(100, "uc4e"),
+        ).unwrap_err();
+        assert_eq!(
+            ContractError::InvalidFunds { expected: coin(150, "uc4e"), received: coin(100, "uc4e") },
+            err.downcast().unwrap()
+        );
+
+        app.execute_contract(
+            Addr::unchecked(DRIVER_ADDRESS),
+            addr.clone(),
+            &ExecuteMsg::PayDebt { energy_transfer_id: 2 },
+            &coins(150, "uc4e"),
+        ).unwrap();
+
+        assert_eq!(app.wrap().query_balance(OWNER_ADDRESS, "uc4e").unwrap().amount.u128(), 11150);
+        assert_eq!(app.wrap().query_balance(DRIVER_ADDRESS, "uc4e").unwrap().amount.u128(), 8850);
+
+        execute_start_energy_transfer(
+            &mut app,
+            addr.clone(),
+            DRIVER_ADDRESS.to_string(),
+            1,
+            10,
+        );
+    }
+
+    #[test]
+    fn test_debt_totals_per_asset() {
+        let mut deps = mock_dependencies();
+        let driver = Addr::unchecked(DRIVER_ADDRESS);
+        let debts = [(1, "uc4e", 150), (2, "uusdc", 30), (3, "uc4e", 100)];
+        for (energy_transfer_id, denom, amount) in debts {
+            DEBTS.save(deps.as_mut().storage, (&driver, energy_transfer_id), &Debt {
+                energy_transfer_id,
+                driver: DRIVER_ADDRESS.to_string(),
+                owner: OWNER_ADDRESS.to_string(),
+                amount: Uint128::new(amount),
+                asset: Asset::Native(denom.to_string()),
+            }).unwrap();
+        }
+
+        // Amounts in different assets are never added together, even beyond the returned page
+        let res = query::query_debts(deps.as_ref(), DRIVER_ADDRESS.to_string(), None, Some(1)).unwrap();
+        assert_eq!(res.debts.len(), 1);
+        assert_eq!(
+            res.total,
+            vec![
+                AssetAmount { asset: Asset::Native("uc4e".to_string()), amount: Uint128::new(250) },
+                AssetAmount { asset: Asset::Native("uusdc".to_string()), amount: Uint128::new(30) },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_collateral_overflow() {
+        let (mut app, addr) = setup_app();
+
+        app.execute_contract(
+            Addr::unchecked(OWNER_ADDRESS),
+            addr.clone(),
+            &ExecuteMsg::PublishEnergyTransferOffer {
+                charger_id: "charger1".to_string(),
+                location: Location { latitude: "60".to_string(), longitude: "60".to_string() },
+                tariff: Decimal::MAX,
+                name: "offer1".to_string(),
+                plug_type: PlugType::Type1,
+                max_session_duration: None,
+                overconsumption_policy: None,
+                prices: None,
+                fiat_tariff: None,
+            },
+            &[],
+        ).unwrap();
+
+        let err = app.execute_contract(
+            Addr::unchecked(DRIVER_ADDRESS),
+            addr.clone(),
+            &ExecuteMsg::StartEnergyTransfer {
+                driver: DRIVER_ADDRESS.to_string(),
+                energy_transfer_offer_id: 1,
+                energy_to_transfer: u64::MAX,
+                from_deposit: None,
+                denom: None,
+                max_tariff: None,
+            },
+            &[],
+        ).unwrap_err();
+        assert!(matches!(err.downcast().unwrap(), ContractError::Overflow(_)));
+    }
+
+    #[test]
+    fn test_start_energy_transfer_funds() {
+        let (mut app, addr) = setup_app();
+
+        execute_publish_offer(&mut app, addr.clone());
+        app.sudo(BankSudo::Mint { to_address: DRIVER_ADDRESS.to_string(), amount: coins(1000, "uatom") }.into()).unwrap();
+
+        let start = ExecuteMsg::StartEnergyTransfer {
+            driver: DRIVER_ADDRESS.to_string(),
+            energy_transfer_offer_id: 1,
+            energy_to_transfer: 10,
+            from_deposit: None,
+            denom: None,
+            max_tariff: None,
+        };
+
+        let err = app.execute_contract(Addr::unchecked(DRIVER_ADDRESS), addr.clone(), &start, &coins(400, "uc4e")).unwrap_err();
+        assert_eq!(
+            ContractError::InvalidFunds { expected: coin(500, "uc4e"), received: coin(400, "uc4e") },
+            err.downcast().unwrap()
+        );
+
+        let err = app.execute_contract(Addr::unchecked(DRIVER_ADDRESS), addr.clone(), &start, &coins(500, "uatom")).unwrap_err();
+        assert_eq!(
+            ContractError::Payment(PaymentError::MissingDenom("uc4e".to_string())),
+            err.downcast().unwrap()
+        );
+
+        let err = app.execute_contract(
+            Addr::unchecked(DRIVER_ADDRESS),
+            addr.clone(),
+            &start,
+            &[coin(500, "uatom"), coin(500, "uc4e")],
+        ).unwrap_err();
+        assert_eq!(ContractError::Payment(PaymentError::MultipleDenoms {}), err.downcast().unwrap());
+
+        // Overpayment is refunded to the sender
+        app.execute_contract(Addr::unchecked(DRIVER_ADDRESS), addr.clone(), &start, &coins(600, "uc4e")).unwrap();
+        assert_eq!(app.wrap().query_balance(DRIVER_ADDRESS, "uc4e").unwrap().amount.u128(), 9500);
+
+        let query_res: EnergyTransferResponse = app
+            .wrap()
+            .query_wasm_smart(addr.clone(), &QueryMsg::EnergyTransfer { id: 1 })
+            .unwrap();
+        assert_eq!(query_res.energy_transfer.collateral, Uint128::new(500));
+    }
+
+    #[test]
+    fn test_sponsored_energy_transfer() {
+        let (mut app, addr) = setup_app();
+
+        execute_publish_offer(&mut app, addr.clone());
+        app.sudo(BankSudo::Mint { to_address: SPONSOR_ADDRESS.to_string(), amount: coins(1000, "uc4e") }.into()).unwrap();
+
+        let start = ExecuteMsg::StartEnergyTransfer {
+            driver: DRIVER_ADDRESS.to_string(),
+            energy_transfer_offer_id: 1,
+            energy_to_transfer: 10,
+            from_deposit: None,
+            denom: None,
+            max_tariff: None,
+        };
+        let res = app.execute_contract(Addr::unchecked(SPONSOR_ADDRESS), addr.clone(), &start, &coins(500, "uc4e")).unwrap();
+        let event = res.events.iter().find(|event| event.ty == "wasm-start_energy_transfer").unwrap();
+        let attribute = |key: &str| event.attributes.iter().find(|attr| attr.key == key).map(|attr| attr.value.clone());
+        assert_eq!(attribute("driver"), Some(DRIVER_ADDRESS.to_string()));
+        assert_eq!(attribute("payer"), Some(SPONSOR_ADDRESS.to_string()));
+
+        execute_energy_transfer_started(&mut app, addr.clone(), 1);
+
+        // The driver cannot top up collateral that is refunded to the sponsor
+        let err = app.execute_contract(
+            Addr::unchecked(DRIVER_ADDRESS),
+            addr.clone(),
+            &ExecuteMsg::ExtendEnergyTransfer { energy_transfer_id: 1, additional_energy: 2 },
+            &coins(100, "uc4e"),
+        ).unwrap_err();
+        assert_eq!(ContractError::InvalidSigner(DRIVER_ADDRESS.to_string()), err.downcast().unwrap());
+
+        app.execute_contract(
+            Addr::unchecked(SPONSOR_ADDRESS),
+            addr.clone(),
+            &ExecuteMsg::ExtendEnergyTransfer { energy_transfer_id: 1, additional_energy: 2 },
+            &coins(100, "uc4e"),
+        ).unwrap();
+
+        app.execute_contract(
+            Addr::unchecked(CONNECTOR_ADDRESS),
+            addr.clone(),
+            &ExecuteMsg::EnergyTransferCompleted { energy_transfer_id: 1, used_service_units: 8 },
+            &[],
+        ).unwrap();
+
+        // Unused collateral goes back to the sponsor, the driver's balance is untouched
+        assert_eq!(app.wrap().query_balance(SPONSOR_ADDRESS, "uc4e").unwrap().amount.u128(), 600);
+        assert_eq!(app.wrap().query_balance(OWNER_ADDRESS, "uc4e").unwrap().amount.u128(), 10400);
+        assert_eq!(app.wrap().query_balance(DRIVER_ADDRESS, "uc4e").unwrap().amount.u128(), 10000);
+
+        // A session cancelled by its driver is refunded to the sponsor as well
+        app.execute_contract(Addr::unchecked(SPONSOR_ADDRESS), addr.clone(), &start, &coins(500, "uc4e")).unwrap();
+        execute_cancel_energy_transfer(&mut app, addr.clone(), DRIVER_ADDRESS.to_string(), 2, 1);
+        assert_eq!(app.wrap().query_balance(SPONSOR_ADDRESS, "uc4e").unwrap().amount.u128(), 600);
+
+        execute_start_energy_transfer(&mut app, addr.clone(), DRIVER_ADDRESS.to_string(), 1, 10);
+
+        let query_res: EnergyTransfersByPayerResponse = app
+            .wrap()
+            .query_wasm_smart(addr.clone(), &QueryMsg::PayerEnergyTransfers { payer: SPONSOR_ADDRESS.to_string(), start_after: None, limit: None })
+            .unwrap();
+        assert_eq!(query_res.energy_transfers.iter().map(|transfer| transfer.id).collect::<Vec<_>>(), vec![1, 2]);
+        assert!(query_res.energy_transfers.iter().all(|transfer| transfer.driver == DRIVER_ADDRESS));
+
+        let query_res: EnergyTransfersByPayerResponse = app
+            .wrap()
+            .query_wasm_smart(addr.clone(), &QueryMsg::PayerEnergyTransfers { payer: DRIVER_ADDRESS.to_string(), start_after: None, limit: None })
+            .unwrap();
+        assert_eq!(query_res.energy_transfers.iter().map(|transfer| transfer.id).collect::<Vec<_>>(), vec![3]);
+        assert_solvent(&app, &addr, &[OWNER_ADDRESS, DRIVER_ADDRESS, SPONSOR_ADDRESS]);
+    }
+
+    #[test]
+    fn test_protocol_fee() {
+        let (mut app, addr) = setup_app();
+
+        execute_publish_offer(&mut app, addr.clone());
+        app.execute_contract(
+            Addr::unchecked(CONTRACT_CREATOR_ADDRESS),
+            addr.clone(),
+            &ExecuteMsg::UpdateConfig {
+                fee_bps: Some(333),
+                treasury: Some("treasury".to_string()),
+                limits: None,
+                start_timeout: None,
+                timeout_policy: None,
+                payout_mode: None,
+                accepted_denoms: None,
+                cw20_tokens: None,
+                oracle: None,
+                max_price_age: None,
+            },
+            &[],
+        ).unwrap();
+
+        let balances = |app: &App| -> Vec<u128> {
+            [OWNER_ADDRESS, "treasury", DRIVER_ADDRESS]
+                .iter()
+                .map(|address| app.wrap().query_balance(*address, "uc4e").unwrap().amount.u128())
+                .collect()
+        };
+
+        // 10 units at a tariff of 50 lock 500, of which (used * 50 * 333 / 10000) rounded down is the fee
+        let cases = [(1, 1), (3, 4), (7, 11), (10, 16)];
+        let mut total_fees = 0;
+        for (energy_transfer_id, (used_service_units, expected_fee)) in (1u64..).zip(cases) {
+            execute_start_energy_transfer(&mut app, addr.clone(), DRIVER_ADDRESS.to_string(), 1, 10);
+            execute_energy_transfer_started(&mut app, addr.clone(), energy_transfer_id);
+
+            let before = balances(&app);
+            let res = app.execute_contract(
+                Addr::unchecked(CONNECTOR_ADDRESS),
+                addr.clone(),
+                &ExecuteMsg::EnergyTransferCompleted { energy_transfer_id, used_service_units },
+                &[],
+            ).unwrap();
+            let after = balances(&app);
+
+            let charged = 50 * used_service_units as u128;
+            assert_eq!(after[0] - before[0], charged - expected_fee);
+            assert_eq!(after[1] - before[1], expected_fee);
+            assert_eq!(after[2] - before[2], 500 - charged);
+            // Nothing is lost to rounding, the collateral is paid out in full
+            assert_eq!(after.iter().sum::<u128>() - before.iter().sum::<u128>(), 500);
+
+            let event = res.events.iter().find(|event| event.ty == "wasm-energy_transfer_completed").unwrap();
+            let fee_attribute = event.attributes.iter().find(|attr| attr.key == "fee_amount").unwrap();
+            assert_eq!(fee_attribute.value, expected_fee.to_string());
+
+            let query_res: EnergyTransferResponse = app
+                .wrap()
+                .query_wasm_smart(addr.clone(), &QueryMsg::EnergyTransfer { id: energy_transfer_id })
+                .unwrap();
+            assert_eq!(query_res.energy_transfer.fee_amount, Uint128::new(expected_fee));
+            total_fees += expected_fee;
+        }
+
+        let fee_info: FeeInfoResponse = app.wrap().query_wasm_smart(addr.clone(), &QueryMsg::FeeInfo { asset: None }).unwrap();
+        assert_eq!(
+            fee_info,
+            FeeInfoResponse {
+                fee_bps: 333,
+                max_fee_bps: execute::MAX_FEE_BPS,
+                treasury: Some(Addr::unchecked("treasury")),
+                total_collected: Uint128::new(total_fees),
+            }
+        );
+
+        // Fees are rounded down, so small amounts carry no fee at all
+        for amount in [0u128, 1, 29, 30, 31, 9_999, 10_000, 10u128.pow(30)] {
+            for fee_bps in [0, 1, 333, execute::MAX_FEE_BPS] {
+                let fee = execute::protocol_fee(fee_bps, Uint128::new(amount)).u128();
+                let scaled = amount * fee_bps as u128;
+                assert!(fee * 10_000 <= scaled && scaled < (fee + 1) * 10_000);
+            }
+        }
+        assert!(execute::protocol_fee(333, Uint128::MAX) < Uint128::MAX);
+    }
+
+    /// The contract holds exactly the collateral of open sessions plus the claimable
+    /// balances, earnings and free deposits it owes to the given addresses. The contract
+    /// itself never checks this, so every flow that moves funds should call it.
+    fn assert_solvent(app: &App, addr: &Addr, addresses: &[&str]) {
+        assert_solvent_in(app, addr, &Asset::Native("uc4e".to_string()), addresses);
+    }
+
+    fn assert_solvent_in(app: &App, addr: &Addr, asset: &Asset, addresses: &[&str]) {
+        let mut escrowed = Uint128::zero();
+        let mut start_after = None;
+        loop {
+            let page: AllEnergyTransfersResponse = app
+                .wrap()
+                .query_wasm_smart(addr.clone(), &QueryMsg::AllEnergyTransfers { start_after, limit: Some(30) })
+                .unwrap();
+            escrowed += page.energy_transfers
+                .iter()
+                .filter(|transfer| matches!(transfer.status, TransferStatus::Requested | TransferStatus::Ongoing))
+                .filter(|transfer| transfer.asset == *asset)
+                .map(|transfer| transfer.collateral)
+                .sum::<Uint128>();
+            start_after = match page.energy_transfers.last() {
+                Some(transfer) => Some(transfer.id),
+                None => break,
+            };
+        }
+
+        let mut owed = Uint128::zero();
+        for address in addresses {
+            let claimable: ClaimableResponse = app
+                .wrap()
+                .query_wasm_smart(addr.clone(), &QueryMsg::Claimable { address: address.to_string(), asset: Some(asset.clone()) })
+                .unwrap();
+            let balance: BalanceResponse = app
+                .wrap()
+                .query_wasm_smart(addr.clone(), &QueryMsg::Balance { address: address.to_string(), asset: Some(asset.clone()) })
+                .unwrap();
+            owed += claimable.amount + balance.amount;
+            // Deposits are only taken in the native denom
+            if let Asset::Native(_) = asset {
+                let deposit: DepositResponse = app
+                    .wrap()
+                    .query_wasm_smart(addr.clone(), &QueryMsg::Deposit { address: address.to_string() })
+                    .unwrap();
+                owed += deposit.free;
+            }
+        }
+
+        let held = match asset {
+            Asset::Native(denom) => app.wrap().query_balance(addr, denom).unwrap().amount,
+            Asset::Cw20(token) => cw20_balance(app, token, addr.as_str()),
+        };
+        assert_eq!(held, escrowed + owed);
+    }
+
+    fn cw20_balance(app: &App, token: &Addr, address: &str) -> Uint128 {
+        let resp: cw20::BalanceResponse = app
+            .wrap()
+            .query_wasm_smart(token.clone(), &cw20::Cw20QueryMsg::Balance { address: address.to_string() })
+            .unwrap();
+        resp.balance
+    }
+
+    fn setup_cw20(app: &mut App, symbol: &str, initial_balances: &[(&str, u128)]) -> Addr {
+        let code = ContractWrapper::new(
+            cw20_base::contract::execute,
+            cw20_base::contract::instantiate,
+            cw20_base::contract::query,
+        );
+        let code_id = app.store_code(Box::new(code));
+        app.instantiate_contract(
+            code_id,
+            Addr::unchecked(CONTRACT_CREATOR_ADDRESS),
+            &cw20_base::msg::InstantiateMsg {
+                name: format!("{} stablecoin", symbol),
+                symbol: symbol.to_string(),
+                decimals: 6,
+                initial_balances: initial_balances
+                    .iter()
+                    .map(|(address, amount)| cw20::Cw20Coin { address: address.to_string(), amount: Uint128::new(*amount) })
+                    .collect(),
+                mint: None,
+                marketing: None,
+            },
+            &[],
+            symbol,
+            None,
+        ).unwrap()
+    }
+
+    fn send_cw20(app: &mut App, token: &Addr, sender: &str, contract: &Addr, amount: u128, msg: &ReceiveMsg) -> Result<AppResponse, ContractError> {
+        app.execute_contract(
+            Addr::unchecked(sender),
+            token.clone(),
+            &cw20::Cw20ExecuteMsg::Send {
+                contract: contract.to_string(),
+                amount: Uint128::new(amount),
+                msg: to_json_binary(msg).unwrap(),
+            },
+            &[],
+        ).map_err(|err| err.downcast().unwrap())
+    }
+
+    #[test]
+    fn test_cw20_energy_transfer() {
+        let (mut app, addr) = setup_app();
+        let token = setup_cw20(&mut app, "USDX", &[(SPONSOR_ADDRESS, 1000), (DRIVER_ADDRESS, 1000)]);
+        let other_token = setup_cw20(&mut app, "USDY", &[(DRIVER_ADDRESS, 1000)]);
+        let asset = Asset::Cw20(token.clone());
+        let addresses = [OWNER_ADDRESS, DRIVER_ADDRESS, SPONSOR_ADDRESS, "treasury"];
+
+        execute_publish_offer(&mut app, addr.clone());
+        app.execute_contract(
+            Addr::unchecked(CONTRACT_CREATOR_ADDRESS),
+            addr.clone(),
+            &ExecuteMsg::UpdateConfig {
+                fee_bps: Some(100),
+                treasury: Some("treasury".to_string()),
+                limits: None,
+                start_timeout: None,
+                timeout_policy: None,
+                payout_mode: None,
+                accepted_denoms: None,
+                cw20_tokens: Some(vec![token.to_string()]),
+                oracle: None,
+                max_price_age: None,
+            },
+            &[],
+        ).unwrap();
+        app.execute_contract(
+            Addr::unchecked(OWNER_ADDRESS),
+            addr.clone(),
+            &ExecuteMsg::UpdateEnergyTransferOffer {
+                energy_transfer_offer_id: 1,
+                tariff: None,
+                name: None,
+                location: None,
+                plug_type: None,
+                max_session_duration: None,
+                overconsumption_policy: None,
+                prices: Some(vec![Price { asset: asset.clone(), tariff: Decimal::from_ratio(50u128, 1u128) }]),
+                fiat_tariff: None,
+                clear_fiat_tariff: None,
+            },
+            &[],
+        ).unwrap();
+
+        let start = ReceiveMsg::StartEnergyTransfer {
+            driver: DRIVER_ADDRESS.to_string(),
+            energy_transfer_offer_id: 1,
+            energy_to_transfer: 10,
+            max_tariff: None,
+        };
+
+        let err = send_cw20(&mut app, &other_token, DRIVER_ADDRESS, &addr, 500, &start).unwrap_err();
+        assert_eq!(ContractError::UnsupportedAsset(other_token.to_string()), err);
+
+        // Overpayment is refunded in the token
+        send_cw20(&mut app, &token, SPONSOR_ADDRESS, &addr, 600, &start).unwrap();
+        assert_eq!(cw20_balance(&app, &token, SPONSOR_ADDRESS), Uint128::new(500));
+        assert_solvent_in(&app, &addr, &asset, &addresses);
+
+        let query_res: EnergyTransferResponse = app
+            .wrap()
+            .query_wasm_smart(addr.clone(), &QueryMsg::EnergyTransfer { id: 1 })
+            .unwrap();
+        assert_eq!(query_res.energy_transfer.asset, asset);
+        assert_eq!(query_res.energy_transfer.payer, SPONSOR_ADDRESS);
+        assert_eq!(query_res.energy_transfer.collateral, Uint128::new(500));
+
+        execute_energy_transfer_started(&mut app, addr.clone(), 1);
+
+        // A top-up has to be paid in the asset of the session
+        app.sudo(BankSudo::Mint { to_address: SPONSOR_ADDRESS.to_string(), amount: coins(100, "uc4e") }.into()).unwrap();
+        let err = app.execute_contract(
+            Addr::unchecked(SPONSOR_ADDRESS),
+            addr.clone(),
+            &ExecuteMsg::ExtendEnergyTransfer { energy_transfer_id: 1, additional_energy: 2 },
+            &coins(100, "uc4e"),
+        ).unwrap_err();
+        assert_eq!(
+            ContractError::InvalidFunds { expected: coin(100, token.as_str()), received: coin(100, "uc4e") },
+            err.downcast().unwrap()
+        );
+
+        send_cw20(
+            &mut app,
+            &token,
+            SPONSOR_ADDRESS,
+            &addr,
+            100,
+            &ReceiveMsg::ExtendEnergyTransfer { energy_transfer_id: 1, additional_energy: 2 },
+        ).unwrap();
+        assert_solvent_in(&app, &addr, &asset, &addresses);
+
+        app.execute_contract(
+            Addr::unchecked(CONNECTOR_ADDRESS),
+            addr.clone(),
+            &ExecuteMsg::EnergyTransferCompleted { energy_transfer_id: 1, used_service_units: 8 },
+            &[],
+        ).unwrap();
+
+        // Owner, treasury and payer are all paid in the token, native balances are untouched
+        assert_eq!(cw20_balance(&app, &token, OWNER_ADDRESS), Uint128::new(396));
+        assert_eq!(cw20_balance(&app, &token, "treasury"), Uint128::new(4));
+        assert_eq!(cw20_balance(&app, &token, SPONSOR_ADDRESS), Uint128::new(600));
+        assert_eq!(app.wrap().query_balance(OWNER_ADDRESS, "uc4e").unwrap().amount.u128(), 10000);
+        assert_eq!(cw20_balance(&app, &token, addr.as_str()), Uint128::zero());
+        assert_solvent_in(&app, &addr, &asset, &addresses);
+        assert_solvent(&app, &addr, &addresses);
+
+        let fee_info: FeeInfoResponse = app
+            .wrap()
+            .query_wasm_smart(addr.clone(), &QueryMsg::FeeInfo { asset: Some(asset.clone()) })
+            .unwrap();
+        assert_eq!(fee_info.total_collected, Uint128::new(4));
+        let fee_info: FeeInfoResponse = app.wrap().query_wasm_smart(addr.clone(), &QueryMsg::FeeInfo { asset: None }).unwrap();
+        assert_eq!(fee_info.total_collected, Uint128::zero());
+    }
+
+    #[test]
+    fn test_multi_currency_offer() {
+        let (mut app, addr) = setup_app();
+        let usdc = Asset::Native("uusdc".to_string());
+        let addresses = [OWNER_ADDRESS, DRIVER_ADDRESS];
+
+        execute_publish_offer(&mut app, addr.clone());
+        let set_prices = |prices: Vec<Price>| ExecuteMsg::UpdateEnergyTransferOffer {
+            energy_transfer_offer_id: 1,
+            tariff: None,
+            name: None,
+            location: None,
+            plug_type: None,
+            max_session_duration: None,
+            overconsumption_policy: None,
+            prices: Some(prices),
+            fiat_tariff: None,
+            clear_fiat_tariff: None,
+        };
+        let usdc_price = Price { asset: usdc.clone(), tariff: Decimal::from_ratio(20u128, 1u128) };
+
+        // Prices are limited to the admin's whitelist
+        let err = app.execute_contract(Addr::unchecked(OWNER_ADDRESS), addr.clone(), &set_prices(vec![usdc_price.clone()]), &[]).unwrap_err();
+        assert_eq!(ContractError::UnsupportedAsset("uusdc".to_string()), err.downcast().unwrap());
+
+        app.execute_contract(
+            Addr::unchecked(CONTRACT_CREATOR_ADDRESS),
+            addr.clone(),
+            &ExecuteMsg::UpdateConfig {
+                fee_bps: None,
+                treasury: None,
+                limits: None,
+                start_timeout: None,
+                timeout_policy: None,
+                payout_mode: None,
+                accepted_denoms: Some(vec!["uusdc".to_string(), "ueur".to_string()]),
+                cw20_tokens: None,
+                oracle: None,
+                max_price_age: None,
+            },
+            &[],
+        ).unwrap();
+
+        let err = app.execute_contract(
+            Addr::unchecked(OWNER_ADDRESS),
+            addr.clone(),
+            &set_prices(vec![usdc_price.clone(), usdc_price.clone()]),
+            &[],
+        ).unwrap_err();
+        assert_eq!(ContractError::DuplicatePrice("uusdc".to_string()), err.downcast().unwrap());
+
+        // The native denom is priced by the offer's tariff
+        let native_price = Price { asset: Asset::Native("uc4e".to_string()), tariff: Decimal::from_ratio(20u128, 1u128) };
+        let err = app.execute_contract(Addr::unchecked(OWNER_ADDRESS), addr.clone(), &set_prices(vec![native_price]), &[]).unwrap_err();
+        assert_eq!(ContractError::DuplicatePrice("uc4e".to_string()), err.downcast().unwrap());
+
+        app.execute_contract(Addr::unchecked(OWNER_ADDRESS), addr.clone(), &set_prices(vec![usdc_price.clone()]), &[]).unwrap();
+        let query_res: EnergyTransferOfferResponse = app
+            .wrap()
+            .query_wasm_smart(addr.clone(), &QueryMsg::EnergyTransferOffer { id: 1 })
+            .unwrap();
+        assert_eq!(query_res.energy_transfer_offer.prices, vec![usdc_price]);
+
+        app.sudo(BankSudo::Mint { to_address: DRIVER_ADDRESS.to_string(), amount: vec![coin(1000, "uusdc"), coin(1000, "ueur")] }.into()).unwrap();
+        let start = |denom: &str, from_deposit: Option<bool>| ExecuteMsg::StartEnergyTransfer {
+            driver: DRIVER_ADDRESS.to_string(),
+            energy_transfer_offer_id: 1,
+            energy_to_transfer: 10,
+            from_deposit,
+            denom: Some(denom.to_string()),
+            max_tariff: None,
+        };
+
+        let err = app.execute_contract(Addr::unchecked(DRIVER_ADDRESS), addr.clone(), &start("ueur", None), &coins(200, "ueur")).unwrap_err();
+        assert_eq!(ContractError::AssetNotPriced(1, "ueur".to_string()), err.downcast().unwrap());
+
+        let err = app.execute_contract(Addr::unchecked(DRIVER_ADDRESS), addr.clone(), &start("uusdc", None), &coins(500, "uc4e")).unwrap_err();
+        assert_eq!(ContractError::Payment(PaymentError::MissingDenom("uusdc".to_string())), err.downcast().unwrap());
+
+        // Deposits are held in the native denom only
+        let err = app.execute_contract(Addr::unchecked(DRIVER_ADDRESS), addr.clone(), &start("uusdc", Some(true)), &[]).unwrap_err();
+        assert_eq!(ContractError::UnsupportedAsset("uusdc".to_string()), err.downcast().unwrap());
+
+        // Collateral is charged at the uusdc price and the excess refunded in uusdc
+        app.execute_contract(Addr::unchecked(DRIVER_ADDRESS), addr.clone(), &start("uusdc", None), &coins(250, "uusdc")).unwrap();
+        assert_eq!(app.wrap().query_balance(DRIVER_ADDRESS, "uusdc").unwrap().amount.u128(), 800);
+        assert_solvent_in(&app, &addr, &usdc, &addresses);
+
+        let query_res: EnergyTransferResponse = app
+            .wrap()
+            .query_wasm_smart(addr.clone(), &QueryMsg::EnergyTransfer { id: 1 })
+            .unwrap();
+        assert_eq!(query_res.energy_transfer.asset, usdc);
+        assert_eq!(query_res.energy_transfer.offered_tariff, Decimal::from_ratio(20u128, 1u128));
+        assert_eq!(query_res.energy_transfer.collateral, Uint128::new(200));
+
+        execute_energy_transfer_started(&mut app, addr.clone(), 1);
+        app.execute_contract(
+            Addr::unchecked(CONNECTOR_ADDRESS),
+            addr.clone(),
+            &ExecuteMsg::EnergyTransferCompleted { energy_transfer_id: 1, used_service_units: 8 },
+            &[],
+        ).unwrap();
+
+        assert_eq!(app.wrap().query_balance(OWNER_ADDRESS, "uusdc").unwrap().amount.u128(), 160);
+        assert_eq!(app.wrap().query_balance(DRIVER_ADDRESS, "uusdc").unwrap().amount.u128(), 840);
+        assert_eq!(app.wrap().query_balance(OWNER_ADDRESS, "uc4e").unwrap().amount.u128(), 10000);
+        assert_solvent_in(&app, &addr, &usdc, &addresses);
+    }
+
+    #[cosmwasm_schema::cw_serde]
+    enum MockOracleMsg {
+        SetPrice { currency: String, asset: String, rate: Decimal, updated_at: Timestamp },
+    }
+
+    const MOCK_ORACLE_PRICES: cw_storage_plus::Map<(&str, &str), OraclePriceResponse> = cw_storage_plus::Map::new("prices");
+
+    fn mock_oracle_instantiate(_deps: DepsMut, _env: Env, _info: MessageInfo, _msg: Empty) -> StdResult<Response> {
+        Ok(Response::new())
+    }
+
+    fn mock_oracle_execute(deps: DepsMut, _env: Env, _info: MessageInfo, msg: MockOracleMsg) -> StdResult<Response> {
+        let MockOracleMsg::SetPrice { currency, asset, rate, updated_at } = msg;
+        MOCK_ORACLE_PRICES.save(deps.storage, (&currency, &asset), &OraclePriceResponse { rate, updated_at })?;
+        Ok(Response::new())
+    }
+
+    fn mock_oracle_query(deps: Deps, _env: Env, msg: OracleQueryMsg) -> StdResult<Binary> {
+        let OracleQueryMsg::Price { currency, asset } = msg;
+        to_json_binary(&MOCK_ORACLE_PRICES.load(deps.storage, (&currency, &asset))?)
+    }
+
+    fn setup_oracle(app: &mut App) -> Addr {
+        let code = ContractWrapper::new(mock_oracle_execute, mock_oracle_instantiate, mock_oracle_query);
+        let code_id = app.store_code(Box::new(code));
+        app.instantiate_contract(code_id, Addr::unchecked(CONTRACT_CREATOR_ADDRESS), &Empty {}, &[], "Oracle", None).unwrap()
+    }
+
+    /// Publishes `rate` for uc4e against EUR as of the current block.
+    fn set_oracle_price(app: &mut App, oracle: &Addr, rate: Decimal) {
+        let updated_at = app.block_info().time;
+        app.execute_contract(
+            Addr::unchecked(CONTRACT_CREATOR_ADDRESS),
+            oracle.clone(),
+            &MockOracleMsg::SetPrice { currency: "EUR".to_string(), asset: "uc4e".to_string(), rate, updated_at },
+            &[],
+        ).unwrap();
+    }
+
+    #[test]
+    fn test_fiat_tariff() {
+        let (mut app, addr) = setup_app();
+        let oracle = setup_oracle(&mut app);
+
+        execute_publish_offer(&mut app, addr.clone());
+        let update_fiat_tariff = |amount: Option<u128>, clear: bool| ExecuteMsg::UpdateEnergyTransferOffer {
+            energy_transfer_offer_id: 1,
+            tariff: None,
+            name: None,
+            location: None,
+            plug_type: None,
+            max_session_duration: None,
+            overconsumption_policy: None,
+            prices: None,
+            fiat_tariff: amount.map(|amount| FiatTariff { currency: "EUR".to_string(), amount: Decimal::from_ratio(amount, 1u128) }),
+            clear_fiat_tariff: Some(clear),
+        };
+        let set_fiat_tariff = |amount: u128| update_fiat_tariff(Some(amount), false);
+
+        let err = app.execute_contract(Addr::unchecked(OWNER_ADDRESS), addr.clone(), &set_fiat_tariff(20), &[]).unwrap_err();
+        assert_eq!(ContractError::MissingOracle, err.downcast().unwrap());
+
+        let set_oracle = |max_price_age: u64| ExecuteMsg::UpdateConfig {
+            fee_bps: None,
+            treasury: None,
+            limits: None,
+            start_timeout: None,
+            timeout_policy: None,
+            payout_mode: None,
+            accepted_denoms: None,
+            cw20_tokens: None,
+            oracle: Some(oracle.to_string()),
+            max_price_age: Some(max_price_age),
+        };
+        let err = app.execute_contract(Addr::unchecked(CONTRACT_CREATOR_ADDRESS), addr.clone(), &set_oracle(u64::MAX), &[]).unwrap_err();
+        assert_eq!(ContractError::PriceAgeLimitExceeded(execute::MAX_PRICE_AGE, u64::MAX), err.downcast().unwrap());
+        app.execute_contract(Addr::unchecked(CONTRACT_CREATOR_ADDRESS), addr.clone(), &set_oracle(300), &[]).unwrap();
+
+        let err = app.execute_contract(Addr::unchecked(DRIVER_ADDRESS), addr.clone(), &set_fiat_tariff(20), &[]).unwrap_err();
+        assert_eq!(ContractError::InvalidSigner(DRIVER_ADDRESS.to_string()), err.downcast().unwrap());
+        let err = app.execute_contract(Addr::unchecked(OWNER_ADDRESS), addr.clone(), &set_fiat_tariff(0), &[]).unwrap_err();
+        assert_eq!(ContractError::ZeroTariff, err.downcast().unwrap());
+
+        // 20 EUR cents at 2.5 uc4e per cent
+        app.execute_contract(Addr::unchecked(OWNER_ADDRESS), addr.clone(), &set_fiat_tariff(20), &[]).unwrap();
+        set_oracle_price(&mut app, &oracle, Decimal::percent(250));
+
+        let start = |max_tariff: Option<u128>| ExecuteMsg::StartEnergyTransfer {
+            driver: DRIVER_ADDRESS.to_string(),
+            energy_transfer_offer_id: 1,
+            energy_to_transfer: 10,
+            from_deposit: None,
+            denom: None,
+            max_tariff: max_tariff.map(|tariff| Decimal::from_ratio(tariff, 1u128)),
+        };
+
+        let err = app.execute_contract(Addr::unchecked(DRIVER_ADDRESS), addr.clone(), &start(Some(40)), &coins(500, "uc4e")).unwrap_err();
+        assert_eq!(ContractError::SlippageExceeded(Decimal::from_ratio(40u128, 1u128), Decimal::from_ratio(50u128, 1u128)), err.downcast().unwrap());
+
+        let updated_at = app.block_info().time;
+        app.update_block(|block| block.time = block.time.plus_seconds(301));
+        let err = app.execute_contract(Addr::unchecked(DRIVER_ADDRESS), addr.clone(), &start(None), &coins(500, "uc4e")).unwrap_err();
+        assert_eq!(ContractError::StalePrice("uc4e".to_string(), updated_at), err.downcast().unwrap());
+
+        // A price dated ahead of the block is not taken as fresh
+        let updated_at = app.block_info().time.plus_seconds(60);
+        app.execute_contract(
+            Addr::unchecked(CONTRACT_CREATOR_ADDRESS),
+            oracle.clone(),
+            &MockOracleMsg::SetPrice { currency: "EUR".to_string(), asset: "uc4e".to_string(), rate: Decimal::percent(300), updated_at },
+            &[],
+        ).unwrap();
+        let err = app.execute_contract(Addr::unchecked(DRIVER_ADDRESS), addr.clone(), &start(None), &coins(500, "uc4e")).unwrap_err();
+        assert_eq!(ContractError::PriceInFuture("uc4e".to_string(), updated_at), err.downcast().unwrap());
+
+        set_oracle_price(&mut app, &oracle, Decimal::percent(300));
+        app.execute_contract(Addr::unchecked(DRIVER_ADDRESS), addr.clone(), &start(Some(60)), &coins(600, "uc4e")).unwrap();
+
+        let query_res: EnergyTransferResponse = app
+            .wrap()
+            .query_wasm_smart(addr.clone(), &QueryMsg::EnergyTransfer { id: 1 })
+            .unwrap();
+        assert_eq!(query_res.energy_transfer.offered_tariff, Decimal::from_ratio(60u128, 1u128));
+        assert_eq!(query_res.energy_transfer.collateral, Uint128::new(600));
+
+        // The session keeps the tariff it was requested at
+        set_oracle_price(&mut app, &oracle, Decimal::percent(500));
+        execute_energy_transfer_started(&mut app, addr.clone(), 1);
+        execute_energy_transfer_completed(
+            &mut app,
+            addr.clone(),
+            1,
+            10,
+            OWNER_ADDRESS.to_string(),
+            DRIVER_ADDRESS.to_string(),
+            10600,
+            9400,
+        );
+        assert_solvent(&app, &addr, &[OWNER_ADDRESS, DRIVER_ADDRESS]);
+
+        let err = app.execute_contract(Addr::unchecked(OWNER_ADDRESS), addr.clone(), &update_fiat_tariff(Some(20), true), &[]).unwrap_err();
+        assert_eq!(
+            ContractError::CustomError { val: "fiat_tariff and clear_fiat_tariff are exclusive".to_string() },
+            err.downcast().unwrap()
+        );
+
+        // Clearing the fiat tariff prices the offer in tokens again
+        app.execute_contract(Addr::unchecked(OWNER_ADDRESS), addr.clone(), &update_fiat_tariff(None, true), &[]).unwrap();
+        let query_res: EnergyTransferOfferResponse = app
+            .wrap()
+            .query_wasm_smart(addr.clone(), &QueryMsg::EnergyTransferOffer { id: 1 })
+            .unwrap();
+        assert_eq!(query_res.energy_transfer_offer.fiat_tariff, None);
+        app.execute_contract(Addr::unchecked(DRIVER_ADDRESS), addr.clone(), &start(None), &coins(500, "uc4e")).unwrap();
+    }
+
+    #[test]
+    fn test_pull_payouts() {
+        let (mut app, addr) = setup_app();
+        let addresses = [OWNER_ADDRESS, DRIVER_ADDRESS, SPONSOR_ADDRESS, "treasury"];
+
+        execute_publish_offer(&mut app, addr.clone());
+        app.execute_contract(
+            Addr::unchecked(CONTRACT_CREATOR_ADDRESS),
+            addr.clone(),
+            &ExecuteMsg::UpdateConfig {
+                fee_bps: Some(100),
+                treasury: Some("treasury".to_string()),
+                limits: None,
+                start_timeout: None,
+                timeout_policy: None,
+                payout_mode: Some(PayoutMode::Pull),
+                accepted_denoms: None,
+                cw20_tokens: None,
+                oracle: None,
+                max_price_age: None,
+            },
+            &[],
+        ).unwrap();
+
+        execute_start_energy_transfer(&mut app, addr.clone(), DRIVER_ADDRESS.to_string(), 1, 10);
+        assert_solvent(&app, &addr, &addresses);
+        execute_energy_transfer_started(&mut app, addr.clone(), 1);
+
+        // The owner's share stays in the contract, the fee and the refund are still sent
+        execute_energy_transfer_completed(
+            &mut app,
+            addr.clone(),
+            1,
+            8,
+            OWNER_ADDRESS.to_string(),
+            DRIVER_ADDRESS.to_string(),
+            10000,
+            9600,
+        );
+        assert_eq!(app.wrap().query_balance("treasury", "uc4e").unwrap().amount.u128(), 4);
+        let balance: BalanceResponse = app
+            .wrap()
+            .query_wasm_smart(addr.clone(), &QueryMsg::Balance { address: OWNER_ADDRESS.to_string(), asset: None })
+            .unwrap();
+        assert_eq!(balance.amount, Uint128::new(396));
+        assert_solvent(&app, &addr, &addresses);
+
+        let withdraw = |amount: u128, recipient: Option<&str>| ExecuteMsg::Withdraw {
+            amount: Uint128::new(amount),
+            recipient: recipient.map(str::to_string),
+            asset: None,
+        };
+
+        let err = app.execute_contract(Addr::unchecked(OWNER_ADDRESS), addr.clone(), &withdraw(0, None), &[]).unwrap_err();
+        assert_eq!(ContractError::ZeroWithdrawal, err.downcast().unwrap());
+
+        let err = app.execute_contract(Addr::unchecked(OWNER_ADDRESS), addr.clone(), &withdraw(500, None), &[]).unwrap_err();
+        assert_eq!(
+            ContractError::InsufficientBalance(Uint128::new(396), Uint128::new(500)),
+            err.downcast().unwrap()
+        );
+
+        let err = app.execute_contract(Addr::unchecked(DRIVER_ADDRESS), addr.clone(), &withdraw(1, None), &[]).unwrap_err();
+        assert_eq!(
+            ContractError::InsufficientBalance(Uint128::zero(), Uint128::new(1)),
+            err.downcast().unwrap()
+        );
+
+        app.execute_contract(Addr::unchecked(OWNER_ADDRESS), addr.clone(), &withdraw(100, Some(SPONSOR_ADDRESS)), &[]).unwrap();
+        assert_eq!(app.wrap().query_balance(SPONSOR_ADDRESS, "uc4e").unwrap().amount.u128(), 100);
+        assert_solvent(&app, &addr, &addresses);
+
+        // Earnings accrued in pull mode stay withdrawable after switching back
+        app.execute_contract(
+            Addr::unchecked(CONTRACT_CREATOR_ADDRESS),
+            addr.clone(),
+            &ExecuteMsg::UpdateConfig {
+                fee_bps: None,
+                treasury: None,
+                limits: None,
+                start_timeout: None,
+                timeout_policy: None,
+                payout_mode: Some(PayoutMode::Push),
+                accepted_denoms: None,
+                cw20_tokens: None,
+                oracle: None,
+                max_price_age: None,
+            },
+            &[],
+        ).unwrap();
+
+        let res = app.execute_contract(Addr::unchecked(OWNER_ADDRESS), addr.clone(), &withdraw(296, None), &[]).unwrap();
+        let event = res.events.iter().find(|event| event.ty == "wasm-withdraw").unwrap();
+        let attribute = |key: &str| event.attributes.iter().find(|attr| attr.key == key).map(|attr| attr.value.clone());
+        assert_eq!(attribute("recipient"), Some(OWNER_ADDRESS.to_string()));
+        assert_eq!(attribute("remaining"), Some("0".to_string()));
+        assert_eq!(app.wrap().query_balance(OWNER_ADDRESS, "uc4e").unwrap().amount.u128(), 10296);
+        assert_solvent(&app, &addr, &addresses);
+        assert!(app.wrap().query_balance(addr.clone(), "uc4e").unwrap().amount.is_zero());
+    }
+
+    #[test]
+    fn test_deposit_energy_transfer() {
+        let (mut app, addr) = setup_app();
+        let addresses = [OWNER_ADDRESS, DRIVER_ADDRESS];
+
+        execute_publish_offer(&mut app, addr.clone());
+
+        let err = app.execute_contract(Addr::unchecked(DRIVER_ADDRESS), addr.clone(), &ExecuteMsg::Deposit {}, &[]).unwrap_err();
+        assert_eq!(ContractError::Payment(PaymentError::NoFunds {}), err.downcast().unwrap());
+
+        app.execute_contract(Addr::unchecked(DRIVER_ADDRESS), addr.clone(), &ExecuteMsg::Deposit {}, &coins(1000, "uc4e")).unwrap();
+        assert_solvent(&app, &addr, &addresses);
+
+        let deposit = |app: &App| -> DepositResponse {
+            app.wrap()
+                .query_wasm_smart(addr.clone(), &QueryMsg::Deposit { address: DRIVER_ADDRESS.to_string() })
+                .unwrap()
+        };
+        assert_eq!(deposit(&app), DepositResponse { free: Uint128::new(1000), locked: Uint128::zero() });
+
+        let start = |energy_to_transfer: u64| ExecuteMsg::StartEnergyTransfer {
+            driver: DRIVER_ADDRESS.to_string(),
+            energy_transfer_offer_id: 1,
+            energy_to_transfer,
+            from_deposit: Some(true),
+            denom: None,
+            max_tariff: None,
+        };
+
+        // Only the driver or a connector may spend the deposit, and without attaching funds
+        let err = app.execute_contract(Addr::unchecked(OWNER_ADDRESS), addr.clone(), &start(10), &[]).unwrap_err();
+        assert_eq!(ContractError::InvalidSigner(OWNER_ADDRESS.to_string()), err.downcast().unwrap());
+
+        let err = app.execute_contract(Addr::unchecked(CONNECTOR_ADDRESS), addr.clone(), &start(10), &coins(500, "uc4e")).unwrap_err();
+        assert_eq!(ContractError::Payment(PaymentError::NonPayable {}), err.downcast().unwrap());
+
+        // A connector needs the driver's approval before spending the deposit
+        let err = app.execute_contract(Addr::unchecked(CONNECTOR_ADDRESS), addr.clone(), &start(10), &[]).unwrap_err();
+        assert_eq!(ContractError::UnapprovedConnector(CONNECTOR_ADDRESS.to_string()), err.downcast().unwrap());
+
+        let err = app.execute_contract(
+            Addr::unchecked(DRIVER_ADDRESS),
+            addr.clone(),
+            &ExecuteMsg::ApproveDepositConnector { connector: OWNER_ADDRESS.to_string() },
+            &[],
+        ).unwrap_err();
+        assert_eq!(ContractError::ConnectorNotFound(OWNER_ADDRESS.to_string()), err.downcast().unwrap());
+
+        app.execute_contract(
+            Addr::unchecked(DRIVER_ADDRESS),
+            addr.clone(),
+            &ExecuteMsg::ApproveDepositConnector { connector: CONNECTOR_ADDRESS.to_string() },
+            &[],
+        ).unwrap();
+        let deposit_connectors = |app: &App, start_after: Option<&str>| -> Vec<Addr> {
+            let resp: DepositConnectorsResponse = app
+                .wrap()
+                .query_wasm_smart(addr.clone(), &QueryMsg::DepositConnectors {
+                    address: DRIVER_ADDRESS.to_string(),
+                    start_after: start_after.map(str::to_string),
+                    limit: None,
+                })
+                .unwrap();
+            resp.connectors
+        };
+        assert_eq!(deposit_connectors(&app, None), vec![Addr::unchecked(CONNECTOR_ADDRESS)]);
+        assert_eq!(deposit_connectors(&app, Some(CONNECTOR_ADDRESS)), Vec::<Addr>::new());
+
+        app.execute_contract(Addr::unchecked(CONNECTOR_ADDRESS), addr.clone(), &start(10), &[]).unwrap();
+        assert_eq!(deposit(&app), DepositResponse { free: Uint128::new(500), locked: Uint128::new(500) });
+        assert_solvent(&app, &addr, &addresses);
+
+        let query_res: EnergyTransferResponse = app
+            .wrap()
+            .query_wasm_smart(addr.clone(), &QueryMsg::EnergyTransfer { id: 1 })
+            .unwrap();
+        assert_eq!(query_res.energy_transfer.payer, DRIVER_ADDRESS);
+        assert!(query_res.energy_transfer.from_deposit);
+
+        execute_energy_transfer_started(&mut app, addr.clone(), 1);
+        app.execute_contract(
+            Addr::unchecked(DRIVER_ADDRESS),
+            addr.clone(),
+            &ExecuteMsg::ExtendEnergyTransfer { energy_transfer_id: 1, additional_energy: 2 },
+            &[],
+        ).unwrap();
+        assert_eq!(deposit(&app), DepositResponse { free: Uint128::new(400), locked: Uint128::new(600) });
+
+        // The owner is paid out, the unused collateral returns to the free balance
+        execute_energy_transfer_completed(
+            &mut app,
+            addr.clone(),
+            1,
+            8,
+            OWNER_ADDRESS.to_string(),
+            DRIVER_ADDRESS.to_string(),
+            10400,
+            9000,
+        );
+        assert_eq!(deposit(&app), DepositResponse { free: Uint128::new(600), locked: Uint128::zero() });
+        assert_solvent(&app, &addr, &addresses);
+
+        let err = app.execute_contract(Addr::unchecked(DRIVER_ADDRESS), addr.clone(), &start(20), &[]).unwrap_err();
+        assert_eq!(
+            ContractError::InsufficientBalance(Uint128::new(600), Uint128::new(1000)),
+            err.downcast().unwrap()
+        );
+
+        app.execute_contract(Addr::unchecked(DRIVER_ADDRESS), addr.clone(), &start(10), &[]).unwrap();
+        execute_cancel_energy_transfer(&mut app, addr.clone(), DRIVER_ADDRESS.to_string(), 2, 1);
+
+        app.execute_contract(
+            Addr::unchecked(DRIVER_ADDRESS),
+            addr.clone(),
+            &ExecuteMsg::RevokeDepositConnector { connector: CONNECTOR_ADDRESS.to_string() },
+            &[],
+        ).unwrap();
+        let err = app.execute_contract(Addr::unchecked(CONNECTOR_ADDRESS), addr.clone(), &start(10), &[]).unwrap_err();
+        assert_eq!(ContractError::UnapprovedConnector(CONNECTOR_ADDRESS.to_string()), err.downcast().unwrap());
+        assert_eq!(deposit(&app), DepositResponse { free: Uint128::new(600), locked: Uint128::zero() });
+        assert_eq!(deposit_connectors(&app, None), Vec::<Addr>::new());
+
+        let err = app.execute_contract(
+            Addr::unchecked(DRIVER_ADDRESS),
+            addr.clone(),
+            &ExecuteMsg::WithdrawDeposit { amount: Uint128::new(700) },
+            &[],
+        ).unwrap_err();
+        assert_eq!(
+            ContractError::InsufficientBalance(Uint128::new(600), Uint128::new(700)),
+            err.downcast().unwrap()
+        );
+
+        app.execute_contract(
+            Addr::unchecked(DRIVER_ADDRESS),
+            addr.clone(),
+            &ExecuteMsg::WithdrawDeposit { amount: Uint128::new(600) },
+            &[],
+        ).unwrap();
+        assert_eq!(deposit(&app), DepositResponse { free: Uint128::zero(), locked: Uint128::zero() });
+        assert_eq!(app.wrap().query_balance(DRIVER_ADDRESS, "uc4e").unwrap().amount.u128(), 9600);
+        assert_solvent(&app, &addr, &addresses);
+        assert!(app.wrap().query_balance(addr.clone(), "uc4e").unwrap().amount.is_zero());
+    }
+
+    /// Amounts moved by a session run at `tariff`, all in uc4e.
+    struct Settlement {
+        collateral: u128,
+        owner: u128,
+        payer: u128,
+        left_in_contract: u128,
+    }
+
+    /// Publishes an offer at `tariff`, funds, extends and completes a session on it.
+    fn run_session(tariff: Decimal, energy_to_transfer: u64, additional_energy: u64, used_service_units: u64) -> Settlement {
+        let (mut app, addr) = setup_app();
+        let balance = |app: &App, address: &str| app.wrap().query_balance(address, "uc4e").unwrap().amount.u128();
+
+        app.execute_contract(
+            Addr::unchecked(OWNER_ADDRESS),
+            addr.clone(),
+            &ExecuteMsg::PublishEnergyTransferOffer {
+                charger_id: "charger1".to_string(),
+                location: Location { latitude: "60".to_string(), longitude: "60".to_string() },
+                tariff,
+                name: "offer1".to_string(),
+                plug_type: PlugType::Type1,
+                max_session_duration: None,
+                overconsumption_policy: None,
+                prices: None,
+                fiat_tariff: None,
+            },
+            &[],
+        ).unwrap();
+
+        let collateral = execute::collateral_amount(tariff, energy_to_transfer).unwrap();
+        let top_up = execute::collateral_amount(tariff, additional_energy).unwrap();
+        let funds = (collateral + top_up).u128();
+        app.sudo(BankSudo::Mint { to_address: SPONSOR_ADDRESS.to_string(), amount: coins(funds, "uc4e") }.into()).unwrap();
+
+        app.execute_contract(
+            Addr::unchecked(SPONSOR_ADDRESS),
+            addr.clone(),
+            &ExecuteMsg::StartEnergyTransfer {
+                driver: DRIVER_ADDRESS.to_string(),
+                energy_transfer_offer_id: 1,
+                energy_to_transfer,
+                from_deposit: None,
+                denom: None,
+                max_tariff: None,
+            },
+            &coins(collateral.u128(), "uc4e"),
+        ).unwrap();
+        execute_energy_transfer_started(&mut app, addr.clone(), 1);
+        if additional_energy > 0 {
+            app.execute_contract(
+                Addr::unchecked(SPONSOR_ADDRESS),
+                addr.clone(),
+                &ExecuteMsg::ExtendEnergyTransfer { energy_transfer_id: 1, additional_energy },
+                &coins(top_up.u128(), "uc4e"),
+            ).unwrap();
+        }
+
+        let owner_before = balance(&app, OWNER_ADDRESS);
+        app.execute_contract(
+            Addr::unchecked(CONNECTOR_ADDRESS),
+            addr.clone(),
+            &ExecuteMsg::EnergyTransferCompleted { energy_transfer_id: 1, used_service_units },
+            &[],
+        ).unwrap();
+
+        Settlement {
+            collateral: funds,
+            owner: balance(&app, OWNER_ADDRESS) - owner_before,
+            payer: balance(&app, SPONSOR_ADDRESS),
+            left_in_contract: balance(&app, addr.as_str()),
+        }
+    }
+
+    #[test]
+    fn test_fractional_tariff() {
+        // 10 units at 0.15 is 1.5, the collateral is rounded up to 2.
+        // The 7 units used cost 1.05, so the owner is paid 1 and the payer gets 1 back
+        let settlement = run_session(Decimal::permille(150), 10, 0, 7);
+        assert_eq!(settlement.collateral, 2);
+        assert_eq!(settlement.owner, 1);
+        assert_eq!(settlement.payer, 1);
+        assert_eq!(settlement.left_in_contract, 0);
+
+        // All 10 units used still cost 1.5, paid to the owner as 1
+        let settlement = run_session(Decimal::permille(150), 10, 0, 10);
+        assert_eq!(settlement.owner, 1);
+        assert_eq!(settlement.payer, 1);
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn test_settlement_pays_out_collateral(
+            tariff_atomics in 1u128..1_000_000_000_000_000_000_000,
+            energy_to_transfer in 1u64..10_000,
+            additional_energy in 0u64..1_000,
+            used_service_units in 0u64..12_000,
+        ) {
+            let tariff = Decimal::from_atomics(tariff_atomics, 18).unwrap();
+            let settlement = run_session(tariff, energy_to_transfer, additional_energy, used_service_units);
+            let scale = 10u128.pow(18);
+
+            // Each part of the collateral is the exact price rounded up
+            for energy in [energy_to_transfer, additional_energy] {
+                let collateral = execute::collateral_amount(tariff, energy).unwrap().u128();
+                prop_assert!(collateral * scale >= tariff_atomics * energy as u128);
+                prop_assert!(collateral == 0 || (collateral - 1) * scale < tariff_atomics * energy as u128);
+            }
+
+            // The owner is paid the exact price of the energy used rounded down, never more
+            let used = used_service_units.min(energy_to_transfer + additional_energy) as u128;
+            prop_assert!(settlement.owner * scale <= tariff_atomics * used);
+            prop_assert!((settlement.owner + 1) * scale > tariff_atomics * used);
+
+            prop_assert_eq!(settlement.owner + settlement.payer, settlement.collateral);
+            prop_assert_eq!(settlement.left_in_contract, 0);
+        }
+    }
+
     pub fn validate_denom(app: &BasicApp, addr: Addr, denom: String) {
         let resp: DenomResponse = app
             .wrap()
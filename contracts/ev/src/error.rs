@@ -1,4 +1,5 @@
-use cosmwasm_std::StdError;
+use cosmwasm_std::{Coin, Decimal, OverflowError, StdError, Timestamp, Uint128};
+use cw_utils::PaymentError;
 use thiserror::Error;
 use crate::msg::{ChargerStatus, TransferStatus};
 
@@ -7,6 +8,9 @@ pub enum ContractError {
     #[error("{0}")]
     Std(#[from] StdError),
 
+    #[error("{0}")]
+    Overflow(#[from] OverflowError),
+
     #[error("Unauthorized")]
     Unauthorized {},
 
@@ -22,9 +26,18 @@ pub enum ContractError {
     #[error("Invalid charger status. Expected {0} or {1} got {2}")]
     InvalidChargerMultipleStatuses(ChargerStatus, ChargerStatus, ChargerStatus),
 
+    #[error("Cannot change charger status from {0} to {1}")]
+    InvalidChargerStatusChange(ChargerStatus, ChargerStatus),
+
+    #[error("Energy transfer {0} is in progress on this charger")]
+    EnergyTransferInProgress(u64),
+
     #[error("Energy offer not found: {0}")]
     EnergyOfferNotFound(u64),
 
+    #[error("Energy offer {0} update changes nothing")]
+    EmptyOfferUpdate(u64),
+
     #[error("Invalid Driver")]
     InvalidDriver,
 
@@ -43,6 +56,138 @@ pub enum ContractError {
     #[error("Invalid energy transfer status. Expected {0} or {1} got {2}")]
     InvalidEnergyTransferMultipleStatuses(TransferStatus, TransferStatus, TransferStatus),
 
-    #[error("Invalid funds. Expected {0} got {1}")]
-    InvalidFunds(String, String),
+    #[error("Energy transfer is not settled, status {0}")]
+    EnergyTransferNotSettled(TransferStatus),
+
+    #[error("Energy transfer {0} cannot be expired before {1}")]
+    StartDeadlineNotReached(u64, Timestamp),
+
+    #[error("Start timeout must be greater than zero")]
+    ZeroStartTimeout,
+
+    #[error("Start timeout exceeds the limit. Max {0} got {1}")]
+    StartTimeoutLimitExceeded(u64, u64),
+
+    #[error("Energy transfer {0} cannot be claimed back before {1}")]
+    SessionNotTimedOut(u64, Timestamp),
+
+    #[error("Max session duration must be greater than zero")]
+    ZeroSessionDuration,
+
+    #[error("Max session duration exceeds the limit. Max {0} got {1}")]
+    SessionDurationLimitExceeded(u64, u64),
+
+    #[error("Energy reading cannot decrease. Previous {0} got {1}")]
+    EnergyReadingDecreased(u64, u64),
+
+    #[error("Energy reading must be newer than {0}, got {1}")]
+    ReadingOutOfOrder(Timestamp, Timestamp),
+
+    #[error("Energy reading is dated in the future: {0}")]
+    ReadingInFuture(Timestamp),
+
+    #[error("{0}")]
+    Payment(#[from] PaymentError),
+
+    #[error("Invalid funds. Expected {expected} got {received}")]
+    InvalidFunds { expected: Coin, received: Coin },
+
+    #[error("Unauthorized: {0} is not the admin")]
+    UnauthorizedAdmin(String),
+
+    #[error("Unauthorized: {0} is not an authorized connector")]
+    UnauthorizedConnector(String),
+
+    #[error("Unauthorized: {0} is not the driver, the payer, the offer owner, a connector or the admin")]
+    UnauthorizedCanceller(String),
+
+    #[error("Unauthorized: {0} is not approved by the driver to spend its deposit")]
+    UnapprovedConnector(String),
+
+    #[error("Unauthorized: {0} is not a connector or the admin")]
+    UnauthorizedRemover(String),
+
+    #[error("Connector not found: {0}")]
+    ConnectorNotFound(String),
+
+    #[error("Unauthorized: {0} is not the pending admin")]
+    UnauthorizedPendingAdmin(String),
+
+    #[error("No admin handover in progress")]
+    NoPendingAdmin,
+
+    #[error("Fee of {0} bps exceeds the maximum of {1} bps")]
+    FeeTooHigh(u64, u64),
+
+    #[error("A treasury must be set to charge a protocol fee")]
+    MissingTreasury,
+
+    #[error("Energy to transfer exceeds the limit. Max {0} got {1}")]
+    EnergyLimitExceeded(u64, u64),
+
+    #[error("Tariff exceeds the limit. Max {0} got {1}")]
+    TariffLimitExceeded(Decimal, Decimal),
+
+    #[error("Contract is paused")]
+    ContractPaused,
+
+    #[error("Payouts are frozen")]
+    PayoutsFrozen,
+
+    #[error("Cannot migrate from contract {0}")]
+    InvalidMigrationContract(String),
+
+    #[error("Cannot migrate from version {0} to older version {1}")]
+    CannotDowngrade(String, String),
+
+    #[error("Invalid version: {0}")]
+    SemVer(String),
+
+    #[error("An admin must be provided to migrate from this version")]
+    MissingMigrationAdmin,
+
+    #[error("Unknown reply id: {0}")]
+    UnknownReplyId(u64),
+
+    #[error("Nothing to claim")]
+    NothingToClaim,
+
+    #[error("Driver {0} has an outstanding debt")]
+    OutstandingDebt(String),
+
+    #[error("No debt recorded for energy transfer {0}")]
+    DebtNotFound(u64),
+
+    #[error("Withdrawal amount must be greater than zero")]
+    ZeroWithdrawal,
+
+    #[error("Insufficient balance. Available {0} requested {1}")]
+    InsufficientBalance(Uint128, Uint128),
+
+    #[error("Token {0} is not accepted for payment")]
+    UnsupportedAsset(String),
+
+    #[error("Energy offer {0} has no price in {1}")]
+    AssetNotPriced(u64, String),
+
+    #[error("Asset {0} is priced more than once")]
+    DuplicatePrice(String),
+
+    #[error("A price oracle must be set to use fiat tariffs")]
+    MissingOracle,
+
+    #[error("Oracle price of {0} is stale, last updated at {1}")]
+    StalePrice(String, Timestamp),
+
+    #[error("Oracle price of {0} is dated in the future at {1}")]
+    PriceInFuture(String, Timestamp),
+
+    #[error("Max price age must be greater than zero")]
+    ZeroPriceAge,
+
+    #[error("Max price age exceeds the limit. Max {0} got {1}")]
+    PriceAgeLimitExceeded(u64, u64),
+
+    #[error("Offered tariff exceeds the accepted maximum. Max {0} got {1}")]
+    SlippageExceeded(Decimal, Decimal),
 }
@@ -1,21 +1,50 @@
-use cosmwasm_std::{ BankMsg, coins, DepsMut, Env, Event, MessageInfo, Response};
+use cosmwasm_std::{Addr, BankMsg, coin, coins, CosmosMsg, Decimal, Deps, DepsMut, Empty, Env, Event, from_json, MessageInfo, OverflowError, OverflowOperation, Response, StdResult, Storage, SubMsg, Timestamp, to_json_binary, Uint128, WasmMsg};
+use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg};
+use cw_utils::{must_pay, nonpayable, one_coin, PaymentError};
 use crate::ContractError;
-use crate::msg::{ChargerStatus, EnergyTransfer, EnergyTransferOffer, Location, PlugType, TransferStatus};
-use crate::state::{DENOM, ENERGY_TRANSFER_COUNT, ENERGY_TRANSFER_OFFER_COUNT, ENERGY_TRANSFER_OFFERS, ENERGY_TRANSFERS};
+use crate::msg::{Asset, ChargerStatus, Config, Connector, ContractStatus, Debt, Deposit, EnergyTransfer, EnergyTransferOffer, FiatTariff, Limits, Location, OraclePriceResponse, OracleQueryMsg, OverconsumptionPolicy, Payout, PayoutMode, PlugType, Price, ReceiveMsg, TimeoutPolicy, TransferStatus};
+use crate::query::{asset_or_native, has_debt};
+use crate::state::{energy_transfer_offers, energy_transfers, CLAIMABLE, CONFIG, CONNECTORS, CONTRACT_STATUS, DEBTS, DEPOSIT_CONNECTORS, DEPOSITS, EARNINGS, ENERGY_TRANSFER_COUNT, FEES_COLLECTED, ENERGY_TRANSFER_OFFER_COUNT, PAYOUT_COUNT, PAYOUTS, PENDING_ADMIN};
 
+pub const MAX_FEE_BPS: u64 = 1000;
+pub const DEFAULT_START_TIMEOUT: u64 = 15 * 60;
+pub const MAX_START_TIMEOUT: u64 = 24 * 60 * 60;
+pub const DEFAULT_MAX_SESSION_DURATION: u64 = 12 * 60 * 60;
+pub const MAX_SESSION_DURATION: u64 = 7 * 24 * 60 * 60;
+pub const DEFAULT_MAX_PRICE_AGE: u64 = 5 * 60;
+pub const MAX_PRICE_AGE: u64 = 24 * 60 * 60;
+
+#[allow(clippy::too_many_arguments)]
 pub fn publish_energy_transfer_offer(
     deps: DepsMut,
     _env: Env,
     info: MessageInfo,
     charger_id: String,
     location: Location,
-    tariff: u64,
+    tariff: Decimal,
     name: String,
     plug_type: PlugType,
+    max_session_duration: Option<u64>,
+    overconsumption_policy: Option<OverconsumptionPolicy>,
+    prices: Option<Vec<Price>>,
+    fiat_tariff: Option<FiatTariff>,
 ) -> Result<Response, ContractError> {
     if charger_id.is_empty() || name.is_empty() {
         return Err(ContractError::CustomError {val: "charger_id and name must not be empty".parse().unwrap() });
     }
+    let overconsumption_policy = overconsumption_policy.unwrap_or_default();
+
+    ensure_not_paused(deps.as_ref())?;
+
+    let config = CONFIG.load(deps.storage)?;
+    let max_session_duration = max_session_duration.unwrap_or(DEFAULT_MAX_SESSION_DURATION);
+    validate_session_duration(&config, max_session_duration)?;
+    validate_tariff(&config, tariff)?;
+    let prices = prices.unwrap_or_default();
+    validate_prices(&config, &prices)?;
+    if let Some(fiat_tariff) = &fiat_tariff {
+        validate_fiat_tariff(&config, fiat_tariff)?;
+    }
 
     let owner = info.sender.to_string();
 
@@ -32,10 +61,16 @@ pub fn publish_energy_transfer_offer(
         tariff,
         name: name.clone(),
         plug_type: plug_type.clone(),
+        revision: 0,
+        current_energy_transfer_id: None,
+        max_session_duration,
+        overconsumption_policy: overconsumption_policy.clone(),
+        prices: prices.clone(),
+        fiat_tariff: fiat_tariff.clone(),
     };
 
     // Save to storage
-    ENERGY_TRANSFER_OFFERS.save(deps.storage, counter, &new_energy_transfer_offer)?;
+    energy_transfer_offers().save(deps.storage, counter, &new_energy_transfer_offer)?;
 
     let events = vec![
         Event::new("publish_energy_transfer_offer")
@@ -44,7 +79,11 @@ pub fn publish_energy_transfer_offer(
             .add_attribute("charger_id", charger_id.clone())
             .add_attribute("tariff", tariff.to_string())
             .add_attribute("name", name.clone())
-            .add_attribute("plug_type", format!("{:?}", plug_type)),
+            .add_attribute("plug_type", format!("{:?}", plug_type))
+            .add_attribute("max_session_duration", max_session_duration.to_string())
+            .add_attribute("overconsumption_policy", overconsumption_policy.to_string())
+            .add_attribute("prices", format_prices(&prices))
+            .add_attribute("fiat_tariff", format_fiat_tariff(&fiat_tariff)),
     ];
 
 
@@ -58,7 +97,7 @@ pub fn remove_energy_offer(
     energy_offer_id: u64,
 ) -> Result<Response, ContractError> {
 
-    let energy_offer = ENERGY_TRANSFER_OFFERS.load(deps.storage, energy_offer_id)
+    let energy_offer = energy_transfer_offers().load(deps.storage, energy_offer_id)
         .map_err(|_| ContractError::EnergyOfferNotFound(energy_offer_id))?;
 
     let sender = info.sender.to_string();
@@ -66,15 +105,11 @@ pub fn remove_energy_offer(
         return Err(ContractError::InvalidSigner(sender));
     }
 
-    if energy_offer.charger_status != ChargerStatus::Active && energy_offer.charger_status != ChargerStatus::Inactive {
-        return Err(ContractError::InvalidChargerMultipleStatuses(
-            ChargerStatus::Active,
-            ChargerStatus::Inactive,
-            energy_offer.charger_status,
-        ))
+    if let Some(energy_transfer_id) = energy_offer.current_energy_transfer_id {
+        return Err(ContractError::EnergyTransferInProgress(energy_transfer_id));
     }
 
-    ENERGY_TRANSFER_OFFERS.remove(deps.storage, energy_offer_id);
+    energy_transfer_offers().remove(deps.storage, energy_offer_id)?;
 
     let events = vec![
         Event::new("remove_energy_offer")
@@ -86,6 +121,206 @@ pub fn remove_energy_offer(
     Ok(Response::new() .add_events(events))
 }
 
+#[allow(clippy::too_many_arguments)]
+pub fn update_energy_transfer_offer(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    energy_transfer_offer_id: u64,
+    tariff: Option<Decimal>,
+    name: Option<String>,
+    location: Option<Location>,
+    plug_type: Option<PlugType>,
+    max_session_duration: Option<u64>,
+    overconsumption_policy: Option<OverconsumptionPolicy>,
+    prices: Option<Vec<Price>>,
+    fiat_tariff: Option<FiatTariff>,
+    clear_fiat_tariff: Option<bool>,
+) -> Result<Response, ContractError> {
+    let mut offer = energy_transfer_offers()
+        .load(deps.storage, energy_transfer_offer_id)
+        .map_err(|_| ContractError::EnergyOfferNotFound(energy_transfer_offer_id))?;
+
+    let sender = info.sender.to_string();
+    if offer.owner != sender {
+        return Err(ContractError::InvalidSigner(sender));
+    }
+    let fiat_tariff = match (fiat_tariff, clear_fiat_tariff.unwrap_or_default()) {
+        (Some(_), true) => {
+            return Err(ContractError::CustomError { val: "fiat_tariff and clear_fiat_tariff are exclusive".to_string() });
+        }
+        (Some(fiat_tariff), false) => Some(Some(fiat_tariff)),
+        (None, true) => Some(None),
+        (None, false) => None,
+    };
+
+    // Fields resent with their current value are not changes
+    let tariff = tariff.filter(|tariff| *tariff != offer.tariff);
+    let name = name.filter(|name| *name != offer.name);
+    let location = location.filter(|location| *location != offer.location);
+    let plug_type = plug_type.filter(|plug_type| *plug_type != offer.plug_type);
+    let max_session_duration = max_session_duration.filter(|max_session_duration| *max_session_duration != offer.max_session_duration);
+    let overconsumption_policy = overconsumption_policy.filter(|overconsumption_policy| *overconsumption_policy != offer.overconsumption_policy);
+    let prices = prices.filter(|prices| *prices != offer.prices);
+    let fiat_tariff = fiat_tariff.filter(|fiat_tariff| *fiat_tariff != offer.fiat_tariff);
+    if tariff.is_none()
+        && name.is_none()
+        && location.is_none()
+        && plug_type.is_none()
+        && max_session_duration.is_none()
+        && overconsumption_policy.is_none()
+        && prices.is_none()
+        && fiat_tariff.is_none()
+    {
+        return Err(ContractError::EmptyOfferUpdate(energy_transfer_offer_id));
+    }
+
+    let mut event = Event::new("update_energy_transfer_offer")
+        .add_attribute("energy_transfer_offer_id", energy_transfer_offer_id.to_string());
+
+    // Sessions already requested keep their own tariff, duration and overconsumption policy
+    if let Some(tariff) = tariff {
+        validate_tariff(&CONFIG.load(deps.storage)?, tariff)?;
+        event = event
+            .add_attribute("old_tariff", offer.tariff.to_string())
+            .add_attribute("new_tariff", tariff.to_string());
+        offer.tariff = tariff;
+    }
+    if let Some(name) = name {
+        if name.is_empty() {
+            return Err(ContractError::CustomError { val: "name must not be empty".to_string() });
+        }
+        event = event
+            .add_attribute("old_name", offer.name.clone())
+            .add_attribute("new_name", name.clone());
+        offer.name = name;
+    }
+    if let Some(location) = location {
+        event = event
+            .add_attribute("old_location", format!("{},{}", offer.location.latitude, offer.location.longitude))
+            .add_attribute("new_location", format!("{},{}", location.latitude, location.longitude));
+        offer.location = location;
+    }
+    if let Some(plug_type) = plug_type {
+        event = event
+            .add_attribute("old_plug_type", format!("{:?}", offer.plug_type))
+            .add_attribute("new_plug_type", format!("{:?}", plug_type));
+        offer.plug_type = plug_type;
+    }
+    if let Some(max_session_duration) = max_session_duration {
+        validate_session_duration(&CONFIG.load(deps.storage)?, max_session_duration)?;
+        event = event
+            .add_attribute("old_max_session_duration", offer.max_session_duration.to_string())
+            .add_attribute("new_max_session_duration", max_session_duration.to_string());
+        offer.max_session_duration = max_session_duration;
+    }
+    if let Some(overconsumption_policy) = overconsumption_policy {
+        event = event
+            .add_attribute("old_overconsumption_policy", offer.overconsumption_policy.to_string())
+            .add_attribute("new_overconsumption_policy", overconsumption_policy.to_string());
+        offer.overconsumption_policy = overconsumption_policy;
+    }
+    if let Some(prices) = prices {
+        validate_prices(&CONFIG.load(deps.storage)?, &prices)?;
+        event = event
+            .add_attribute("old_prices", format_prices(&offer.prices))
+            .add_attribute("new_prices", format_prices(&prices));
+        offer.prices = prices;
+    }
+    if let Some(fiat_tariff) = fiat_tariff {
+        if let Some(fiat_tariff) = &fiat_tariff {
+            validate_fiat_tariff(&CONFIG.load(deps.storage)?, fiat_tariff)?;
+        }
+        event = event
+            .add_attribute("old_fiat_tariff", format_fiat_tariff(&offer.fiat_tariff))
+            .add_attribute("new_fiat_tariff", format_fiat_tariff(&fiat_tariff));
+        offer.fiat_tariff = fiat_tariff;
+    }
+
+    offer.revision += 1;
+    energy_transfer_offers().save(deps.storage, energy_transfer_offer_id, &offer)?;
+
+    Ok(Response::new().add_event(event.add_attribute("revision", offer.revision.to_string())))
+}
+
+/// Owners and their connectors switch a charger between Active, Inactive, Maintenance
+/// and Faulted. While a session is in progress the charger can only be reported Faulted;
+/// the session is then settled as usual and the charger stays Faulted afterwards.
+pub fn set_charger_status(
+    deps: DepsMut,
+    info: MessageInfo,
+    energy_transfer_offer_id: u64,
+    status: ChargerStatus,
+) -> Result<Response, ContractError> {
+    let mut offer = energy_transfer_offers()
+        .load(deps.storage, energy_transfer_offer_id)
+        .map_err(|_| ContractError::EnergyOfferNotFound(energy_transfer_offer_id))?;
+
+    if info.sender.as_str() != offer.owner && !is_connector(deps.as_ref(), &info.sender, &offer.owner)? {
+        return Err(ContractError::InvalidSigner(info.sender.to_string()));
+    }
+
+    let allowed = match status {
+        ChargerStatus::Busy | ChargerStatus::Unspecified => false,
+        ChargerStatus::Faulted => true,
+        _ => offer.current_energy_transfer_id.is_none(),
+    };
+    if !allowed {
+        return Err(ContractError::InvalidChargerStatusChange(offer.charger_status, status));
+    }
+
+    let previous_status = offer.charger_status;
+    offer.charger_status = status;
+    energy_transfer_offers().save(deps.storage, energy_transfer_offer_id, &offer)?;
+
+    let events = vec![
+        Event::new("set_charger_status")
+            .add_attribute("energy_transfer_offer_id", energy_transfer_offer_id.to_string())
+            .add_attribute("charger_id", offer.charger_id)
+            .add_attribute("old_status", previous_status.to_string())
+            .add_attribute("new_status", offer.charger_status.to_string()),
+    ];
+
+    Ok(Response::new().add_events(events))
+}
+
+/// Funds paid along with a message, either attached or received through a cw20 `Send`.
+struct Funds {
+    asset: Asset,
+    amount: Uint128,
+}
+
+/// Where the collateral of a new session comes from.
+enum Funding {
+    /// Native funds attached to the message in the chosen denom.
+    Attached(MessageInfo, String),
+    /// Tokens received through a cw20 `Send`.
+    Received(Funds),
+    /// The driver's deposit.
+    Deposit,
+}
+
+impl Funding {
+    /// Asset the session is paid in, deposits being held in the native denom.
+    fn asset(&self, config: &Config) -> Asset {
+        match self {
+            Funding::Attached(_, denom) => Asset::Native(denom.clone()),
+            Funding::Received(funds) => funds.asset.clone(),
+            Funding::Deposit => Asset::Native(config.denom.clone()),
+        }
+    }
+
+    /// The funds paid for the session, `None` when it is started from a deposit.
+    fn into_funds(self) -> Result<Option<Funds>, ContractError> {
+        match self {
+            Funding::Attached(info, denom) => Ok(Some(Funds { amount: must_pay(&info, &denom)?, asset: Asset::Native(denom) })),
+            Funding::Received(funds) => Ok(Some(funds)),
+            Funding::Deposit => Ok(None),
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn start_energy_transfer(
     deps: DepsMut,
     info: MessageInfo,
@@ -93,10 +328,42 @@ pub fn start_energy_transfer(
     env: Env,
     energy_transfer_offer_id: u64,
     energy_to_transfer: u64,
+    from_deposit: Option<bool>,
+    denom: Option<String>,
+    max_tariff: Option<Decimal>,
+) -> Result<Response, ContractError> {
+    let sender = info.sender.clone();
+    let native_denom = CONFIG.load(deps.storage)?.denom;
+    let funding = if from_deposit.unwrap_or_default() {
+        nonpayable(&info)?;
+        // Deposits are only held in the native denom
+        if let Some(denom) = denom.filter(|denom| *denom != native_denom) {
+            return Err(ContractError::UnsupportedAsset(denom));
+        }
+        Funding::Deposit
+    } else {
+        Funding::Attached(info, denom.unwrap_or(native_denom))
+    };
+    start(deps, env, sender, driver, energy_transfer_offer_id, energy_to_transfer, max_tariff, funding)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn start(
+    deps: DepsMut,
+    env: Env,
+    sender: Addr,
+    driver: String,
+    energy_transfer_offer_id: u64,
+    energy_to_transfer: u64,
+    max_tariff: Option<Decimal>,
+    funding: Funding,
 ) -> Result<Response, ContractError> {
-    validate_start_energy_transfer(&deps, &driver, energy_to_transfer)?;
+    ensure_not_paused(deps.as_ref())?;
+
+    let config = CONFIG.load(deps.storage)?;
+    validate_start_energy_transfer(&deps, &config, &driver, energy_to_transfer)?;
 
-    let mut offer = ENERGY_TRANSFER_OFFERS
+    let mut offer = energy_transfer_offers()
         .load(deps.storage, energy_transfer_offer_id)
         .map_err(|_| ContractError::EnergyOfferNotFound(energy_transfer_offer_id))?;
 
@@ -104,50 +371,211 @@ pub fn start_energy_transfer(
         return Err(ContractError::InvalidChargerStatus(ChargerStatus::Active, offer.charger_status));
     }
 
-    offer.charger_status = ChargerStatus::Busy;
-    ENERGY_TRANSFER_OFFERS.save(deps.storage, energy_transfer_offer_id, &offer)?;
-
     let mut transfer_count = ENERGY_TRANSFER_COUNT.load(deps.storage)?;
     transfer_count += 1;
 
-    let collateral = offer.tariff * energy_to_transfer;
+    offer.charger_status = ChargerStatus::Busy;
+    offer.current_energy_transfer_id = Some(transfer_count);
+    energy_transfer_offers().save(deps.storage, energy_transfer_offer_id, &offer)?;
+
+    let asset = funding.asset(&config);
+    let tariff = offer_tariff(deps.as_ref(), &env, &config, &offer, &asset)?;
+    if let Some(max_tariff) = max_tariff {
+        if tariff > max_tariff {
+            return Err(ContractError::SlippageExceeded(max_tariff, tariff));
+        }
+    }
+    let collateral = collateral_amount(tariff, energy_to_transfer)?;
+    let from_deposit = matches!(funding, Funding::Deposit);
+    let (payer, refund) = match funding.into_funds()? {
+        None => {
+            // Connectors approved by the driver may start sessions from the deposit on its behalf
+            let driver_addr = deps.api.addr_validate(&driver)?;
+            if sender != driver_addr {
+                if !is_connector(deps.as_ref(), &sender, &offer.owner)? {
+                    return Err(ContractError::InvalidSigner(sender.to_string()));
+                }
+                if !DEPOSIT_CONNECTORS.has(deps.storage, (&driver_addr, &sender)) {
+                    return Err(ContractError::UnapprovedConnector(sender.to_string()));
+                }
+            }
+            lock_deposit(deps.storage, &driver_addr, collateral)?;
+            (driver.clone(), None)
+        }
+        Some(funds) => {
+            let excess = collect_payment(&funds, &asset, collateral)?;
+            let refund = refund_excess(deps.storage, transfer_count, sender.as_str(), excess, &asset)?;
+            (sender.to_string(), refund)
+        }
+    };
+
     let energy_transfer = EnergyTransfer {
         id: transfer_count,
         energy_transfer_offer_id,
         charger_id: offer.charger_id.clone(),
         owner: offer.owner.clone(),
         driver,
-        offered_tariff: offer.tariff,
+        payer,
+        offered_tariff: tariff,
         status: TransferStatus::Requested,
         collateral,
         energy_to_transfer,
         energy_transferred: 0,
         paid_date: env.block.time,
+        start_deadline: checked_plus_seconds(env.block.time, config.start_timeout)?,
+        started_at: None,
+        last_reported_at: None,
+        max_session_duration: offer.max_session_duration,
+        overconsumption_policy: offer.overconsumption_policy.clone(),
+        excess_energy: 0,
+        fee_amount: Uint128::zero(),
+        from_deposit,
+        asset,
     };
-    ENERGY_TRANSFERS.save(deps.storage, transfer_count, &energy_transfer)?;
+    energy_transfers().save(deps.storage, transfer_count, &energy_transfer)?;
     ENERGY_TRANSFER_COUNT.save(deps.storage, &transfer_count)?;
 
-    let denom = DENOM.load(deps.storage)?;
-
-    let collateral_coins = coins(collateral.into(), &denom);
-
-    if info.funds != collateral_coins {
-        return Err(ContractError::InvalidFunds(format!("{:?}", collateral_coins), format!("{:?}", info)));
-    }
-
     let events = vec![
         Event::new("start_energy_transfer")
             .add_attribute("energy_transfer_id", energy_transfer.id.to_string())
             .add_attribute("charger_id", offer.charger_id.to_string())
             .add_attribute("energy_transfer_offer_id", energy_transfer.energy_to_transfer.to_string())
-            .add_attribute("new_transfer_id", energy_transfer.id.to_string()),
+            .add_attribute("new_transfer_id", energy_transfer.id.to_string())
+            .add_attribute("driver", energy_transfer.driver.clone())
+            .add_attribute("payer", energy_transfer.payer.clone())
+            .add_attribute("from_deposit", from_deposit.to_string())
+            .add_attribute("asset", energy_transfer.asset.to_string())
+            .add_attribute("offered_tariff", energy_transfer.offered_tariff.to_string()),
     ];
 
-    Ok(Response::new().add_events(events))
+    Ok(Response::new()
+        .add_submessages(refund)
+        .add_events(events)
+    )
+}
+
+fn validate_tariff(config: &Config, tariff: Decimal) -> Result<(), ContractError> {
+    if let Some(max_tariff) = config.limits.max_tariff {
+        if tariff > max_tariff {
+            return Err(ContractError::TariffLimitExceeded(max_tariff, tariff));
+        }
+    }
+    Ok(())
+}
+
+/// Collateral for `energy` at `tariff`, rounded up to whole tokens so that it always
+/// covers `amount_due` for the same energy.
+pub(crate) fn collateral_amount(tariff: Decimal, energy: u64) -> Result<Uint128, OverflowError> {
+    Uint128::from(energy)
+        .checked_mul_ceil(tariff)
+        .map_err(|_| OverflowError::new(OverflowOperation::Mul, energy, tariff))
+}
+
+/// Owner's share for `energy` at `tariff`, rounded down. What is left of the collateral
+/// goes back to the payer.
+pub(crate) fn amount_due(tariff: Decimal, energy: u64) -> Result<Uint128, OverflowError> {
+    Uint128::from(energy)
+        .checked_mul_floor(tariff)
+        .map_err(|_| OverflowError::new(OverflowOperation::Mul, energy, tariff))
+}
+
+/// Prices must be in distinct accepted assets other than the native denom,
+/// which is priced by the offer's `tariff`.
+fn validate_prices(config: &Config, prices: &[Price]) -> Result<(), ContractError> {
+    let mut priced = vec![Asset::Native(config.denom.clone())];
+    for price in prices {
+        ensure_accepted(config, &price.asset)?;
+        if priced.contains(&price.asset) {
+            return Err(ContractError::DuplicatePrice(price.asset.to_string()));
+        }
+        validate_tariff(config, price.tariff)?;
+        priced.push(price.asset.clone());
+    }
+    Ok(())
+}
+
+fn format_prices(prices: &[Price]) -> String {
+    let prices: Vec<String> = prices
+        .iter()
+        .map(|price| format!("{}{}", price.tariff, price.asset))
+        .collect();
+    format!("[{}]", prices.join(","))
+}
+
+/// Price of the offer in `asset`. The asset must still be accepted by the contract.
+fn offer_tariff(deps: Deps, env: &Env, config: &Config, offer: &EnergyTransferOffer, asset: &Asset) -> Result<Decimal, ContractError> {
+    ensure_accepted(config, asset)?;
+    if let Some(fiat_tariff) = &offer.fiat_tariff {
+        return convert_fiat_tariff(deps, env, config, fiat_tariff, asset);
+    }
+    if *asset == Asset::Native(config.denom.clone()) {
+        return Ok(offer.tariff);
+    }
+    offer.prices
+        .iter()
+        .find(|price| price.asset == *asset)
+        .map(|price| price.tariff)
+        .ok_or_else(|| ContractError::AssetNotPriced(offer.id, asset.to_string()))
+}
+
+fn validate_fiat_tariff(config: &Config, fiat_tariff: &FiatTariff) -> Result<(), ContractError> {
+    if config.oracle.is_none() {
+        return Err(ContractError::MissingOracle);
+    }
+    if fiat_tariff.currency.is_empty() {
+        return Err(ContractError::CustomError { val: "currency must not be empty".to_string() });
+    }
+    if fiat_tariff.amount.is_zero() {
+        return Err(ContractError::ZeroTariff);
+    }
+    Ok(())
+}
+
+fn format_fiat_tariff(fiat_tariff: &Option<FiatTariff>) -> String {
+    match fiat_tariff {
+        Some(fiat_tariff) => format!("{}{}", fiat_tariff.amount, fiat_tariff.currency),
+        None => "none".to_string(),
+    }
+}
+
+/// Converts a fiat tariff into `asset` at the oracle's rate, which must be recent enough
+/// and not dated after the current block.
+fn convert_fiat_tariff(deps: Deps, env: &Env, config: &Config, fiat_tariff: &FiatTariff, asset: &Asset) -> Result<Decimal, ContractError> {
+    let oracle = config.oracle.as_ref().ok_or(ContractError::MissingOracle)?;
+    let price: OraclePriceResponse = deps.querier.query_wasm_smart(
+        oracle,
+        &OracleQueryMsg::Price { currency: fiat_tariff.currency.clone(), asset: asset.to_string() },
+    )?;
+    match env.block.time.seconds().checked_sub(price.updated_at.seconds()) {
+        None => return Err(ContractError::PriceInFuture(asset.to_string(), price.updated_at)),
+        Some(age) if age > config.max_price_age => {
+            return Err(ContractError::StalePrice(asset.to_string(), price.updated_at));
+        }
+        Some(_) => {}
+    }
+
+    let tariff = fiat_tariff.amount.checked_mul(price.rate)?;
+    if tariff.is_zero() {
+        return Err(ContractError::ZeroTariff);
+    }
+    validate_tariff(config, tariff)?;
+    Ok(tariff)
+}
+
+fn ensure_accepted(config: &Config, asset: &Asset) -> Result<(), ContractError> {
+    let accepted = match asset {
+        Asset::Native(denom) => *denom == config.denom || config.accepted_denoms.contains(denom),
+        Asset::Cw20(token) => config.cw20_tokens.contains(token),
+    };
+    if !accepted {
+        return Err(ContractError::UnsupportedAsset(asset.to_string()));
+    }
+    Ok(())
 }
 
 fn validate_start_energy_transfer(
     deps: &DepsMut,
+    config: &Config,
     driver: &str,
     energy_to_transfer: u64,
 ) -> Result<(), ContractError> {
@@ -155,31 +583,44 @@ fn validate_start_energy_transfer(
         return Err(ContractError::InvalidDriver);
     }
 
-    deps.api.addr_validate(driver)?;
+    let driver = deps.api.addr_validate(driver)?;
+    if has_debt(deps.as_ref(), &driver) {
+        return Err(ContractError::OutstandingDebt(driver.to_string()));
+    }
 
     if energy_to_transfer == 0 {
         return Err(ContractError::ZeroEnergy);
     }
+
+    if let Some(max_energy) = config.limits.max_energy_to_transfer {
+        if energy_to_transfer > max_energy {
+            return Err(ContractError::EnergyLimitExceeded(max_energy, energy_to_transfer));
+        }
+    }
     Ok(())
 }
 
 pub(crate) fn energy_transfer_started(
     deps: DepsMut,
-    _info: MessageInfo,
+    env: Env,
+    info: MessageInfo,
     energy_transfer_id: u64,
 ) -> Result<Response, ContractError> {
-    let mut energy_transfer: EnergyTransfer = ENERGY_TRANSFERS
+    let mut energy_transfer: EnergyTransfer = energy_transfers()
         .load(deps.storage, energy_transfer_id)
         .map_err(|_| ContractError::EnergyTransferNotFound(energy_transfer_id))?;
 
+    ensure_connector(deps.as_ref(), &info.sender, &energy_transfer.owner)?;
+
     if energy_transfer.status != TransferStatus::Requested {
         return Err(ContractError::InvalidEnergyTransferStatus(TransferStatus::Requested, energy_transfer.status,
         ));
     }
 
     energy_transfer.status = TransferStatus::Ongoing;
+    energy_transfer.started_at = Some(env.block.time);
 
-    ENERGY_TRANSFERS.save(deps.storage, energy_transfer_id, &energy_transfer)?;
+    energy_transfers().save(deps.storage, energy_transfer_id, &energy_transfer)?;
 
     let events = vec![
         Event::new("energy_transfer_started")
@@ -192,37 +633,123 @@ pub(crate) fn energy_transfer_started(
     )
 }
 
+pub fn extend_energy_transfer(
+    deps: DepsMut,
+    info: MessageInfo,
+    energy_transfer_id: u64,
+    additional_energy: u64,
+) -> Result<Response, ContractError> {
+    let funds = if info.funds.is_empty() {
+        None
+    } else {
+        Some(native_funds(&info)?)
+    };
+    extend(deps, info.sender, energy_transfer_id, additional_energy, funds)
+}
+
+fn extend(
+    deps: DepsMut,
+    sender: Addr,
+    energy_transfer_id: u64,
+    additional_energy: u64,
+    funds: Option<Funds>,
+) -> Result<Response, ContractError> {
+    ensure_not_paused(deps.as_ref())?;
+
+    let mut energy_transfer = energy_transfers()
+        .load(deps.storage, energy_transfer_id)
+        .map_err(|_| ContractError::EnergyTransferNotFound(energy_transfer_id))?;
+
+    // Unused collateral goes back to the payer, so only the payer may add to it
+    if sender != energy_transfer.payer {
+        return Err(ContractError::InvalidSigner(sender.to_string()));
+    }
+
+    if energy_transfer.status != TransferStatus::Ongoing {
+        return Err(ContractError::InvalidEnergyTransferStatus(TransferStatus::Ongoing, energy_transfer.status));
+    }
+
+    if additional_energy == 0 {
+        return Err(ContractError::ZeroEnergy);
+    }
+
+    let config = CONFIG.load(deps.storage)?;
+    let energy_to_transfer = energy_transfer.energy_to_transfer
+        .checked_add(additional_energy)
+        .ok_or_else(|| OverflowError::new(OverflowOperation::Add, energy_transfer.energy_to_transfer, additional_energy))?;
+    if let Some(max_energy) = config.limits.max_energy_to_transfer {
+        if energy_to_transfer > max_energy {
+            return Err(ContractError::EnergyLimitExceeded(max_energy, energy_to_transfer));
+        }
+    }
+
+    // The top-up is priced at the tariff locked in when the session was requested
+    let additional_collateral = collateral_amount(energy_transfer.offered_tariff, additional_energy)?;
+    let refund = match (energy_transfer.from_deposit, funds) {
+        (true, None) => {
+            lock_deposit(deps.storage, &sender, additional_collateral)?;
+            None
+        }
+        (true, Some(_)) => return Err(PaymentError::NonPayable {}.into()),
+        (false, Some(funds)) => {
+            let excess = collect_payment(&funds, &energy_transfer.asset, additional_collateral)?;
+            refund_excess(deps.storage, energy_transfer.id, sender.as_str(), excess, &funds.asset)?
+        }
+        (false, None) => return Err(PaymentError::NoFunds {}.into()),
+    };
+
+    energy_transfer.energy_to_transfer = energy_to_transfer;
+    energy_transfer.collateral = energy_transfer.collateral.checked_add(additional_collateral)?;
+    energy_transfers().save(deps.storage, energy_transfer_id, &energy_transfer)?;
+
+    let events = vec![
+        Event::new("extend_energy_transfer")
+            .add_attribute("energy_transfer_id", energy_transfer.id.to_string())
+            .add_attribute("charger_id", energy_transfer.charger_id)
+            .add_attribute("additional_energy", additional_energy.to_string())
+            .add_attribute("energy_to_transfer", energy_transfer.energy_to_transfer.to_string())
+            .add_attribute("collateral", energy_transfer.collateral.to_string()),
+    ];
+
+    Ok(Response::new()
+        .add_submessages(refund)
+        .add_events(events)
+    )
+}
+
 pub fn cancel_energy_transfer(
     deps: DepsMut,
     _env: Env,
-    _info: MessageInfo,
+    info: MessageInfo,
     energy_transfer_id: u64,
 ) -> Result<Response, ContractError> {
-    let mut energy_transfer = ENERGY_TRANSFERS
+    let mut energy_transfer = energy_transfers()
         .load(deps.storage, energy_transfer_id)
         .map_err(|_| ContractError::EnergyTransferNotFound(energy_transfer_id))?;
 
+    // Either side of the session, its payer, its connector or the admin may call it off
+    let sender = info.sender.as_str();
+    if sender != energy_transfer.driver
+        && sender != energy_transfer.payer
+        && sender != energy_transfer.owner
+        && !is_connector(deps.as_ref(), &info.sender, &energy_transfer.owner)?
+        && !is_admin(deps.as_ref(), &info.sender)?
+    {
+        return Err(ContractError::UnauthorizedCanceller(info.sender.to_string()));
+    }
+
+    ensure_payouts_not_frozen(deps.as_ref())?;
+
     if energy_transfer.status != TransferStatus::Requested {
         return Err(ContractError::InvalidEnergyTransferStatus(TransferStatus::Requested, energy_transfer.status));
     }
 
     energy_transfer.status = TransferStatus::Cancelled;
-    ENERGY_TRANSFERS.save(deps.storage, energy_transfer_id, &energy_transfer)?;
+    energy_transfers().save(deps.storage, energy_transfer_id, &energy_transfer)?;
 
-    let mut offer = ENERGY_TRANSFER_OFFERS
-        .load(deps.storage, energy_transfer.energy_transfer_offer_id)
-        .map_err(|_| ContractError::EnergyOfferNotFound(energy_transfer.energy_transfer_offer_id))?;
+    release_charger(deps.storage, energy_transfer.energy_transfer_offer_id)?;
 
-    offer.charger_status = ChargerStatus::Active;
-    ENERGY_TRANSFER_OFFERS.save(deps.storage, energy_transfer.energy_transfer_offer_id, &offer)?;
-
-    let denom = DENOM.load(deps.storage)?;
-    let collateral_coins = coins(energy_transfer.collateral.into(), &denom);
-
-    let bank_msg = BankMsg::Send {
-        to_address: energy_transfer.driver.clone(),
-        amount: collateral_coins,
-    };
+    let refund = refund_payer(deps.storage, &energy_transfer, energy_transfer.collateral)?;
 
     let events = vec![
         Event::new("cancel_energy_transfer")
@@ -232,96 +759,1041 @@ pub fn cancel_energy_transfer(
     ];
 
     Ok(Response::new()
-        .add_message(bank_msg)
+        .add_submessages(refund)
         .add_events(events)
     )
 }
 
-pub fn energy_transfer_completed(
+pub fn expire_energy_transfer(
     deps: DepsMut,
     env: Env,
-    _info: MessageInfo,
     energy_transfer_id: u64,
-    used_service_units: u64,
 ) -> Result<Response, ContractError> {
-    let mut energy_transfer = ENERGY_TRANSFERS
+    let mut energy_transfer = energy_transfers()
         .load(deps.storage, energy_transfer_id)
         .map_err(|_| ContractError::EnergyTransferNotFound(energy_transfer_id))?;
 
-    if energy_transfer.status != TransferStatus::Requested && energy_transfer.status != TransferStatus::Ongoing {
-        return Err(ContractError::InvalidEnergyTransferMultipleStatuses (TransferStatus::Requested, TransferStatus::Ongoing, energy_transfer.status));
+    ensure_payouts_not_frozen(deps.as_ref())?;
+
+    if energy_transfer.status != TransferStatus::Requested {
+        return Err(ContractError::InvalidEnergyTransferStatus(TransferStatus::Requested, energy_transfer.status));
     }
 
-    let mut amount_to_transfer_to_owner = energy_transfer.collateral;
+    if env.block.time <= energy_transfer.start_deadline {
+        return Err(ContractError::StartDeadlineNotReached(energy_transfer_id, energy_transfer.start_deadline));
+    }
 
-    let mut bank_messages: Vec<BankMsg> = vec![];
-    let denom = DENOM.load(deps.storage)?;
+    energy_transfer.status = TransferStatus::Expired;
+    energy_transfers().save(deps.storage, energy_transfer_id, &energy_transfer)?;
 
-    if energy_transfer.energy_to_transfer > used_service_units {
-        // Transfer the remaining collateral to the owner
-        amount_to_transfer_to_owner = energy_transfer.offered_tariff * used_service_units;
+    release_charger(deps.storage, energy_transfer.energy_transfer_offer_id)?;
 
-        // Transfer the remaining collateral to the driver
-        let amount_to_transfer_to_driver = energy_transfer.collateral - amount_to_transfer_to_owner;
-        bank_messages.push(BankMsg::Send {
-            to_address: energy_transfer.driver.clone(),
-            amount: coins(amount_to_transfer_to_driver.into(), &denom),
-        });
+    let refund = refund_payer(deps.storage, &energy_transfer, energy_transfer.collateral)?;
+
+    let events = vec![
+        Event::new("expire_energy_transfer")
+            .add_attribute("energy_transfer_id", energy_transfer.id.to_string())
+            .add_attribute("charger_id", energy_transfer.charger_id)
+            .add_attribute("start_deadline", energy_transfer.start_deadline.to_string()),
+    ];
+
+    Ok(Response::new()
+        .add_submessages(refund)
+        .add_events(events)
+    )
+}
+
+/// Settles a session the connector stopped reporting on, according to the configured
+/// `TimeoutPolicy`. The charger is flagged Faulted so the owner has to review it
+/// before it takes new sessions.
+pub fn claim_timed_out_energy_transfer(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    energy_transfer_id: u64,
+) -> Result<Response, ContractError> {
+    let mut energy_transfer = energy_transfers()
+        .load(deps.storage, energy_transfer_id)
+        .map_err(|_| ContractError::EnergyTransferNotFound(energy_transfer_id))?;
+
+    if info.sender != energy_transfer.driver && info.sender != energy_transfer.payer {
+        return Err(ContractError::InvalidSigner(info.sender.to_string()));
     }
 
-    if amount_to_transfer_to_owner != 0 {
-        bank_messages.push(BankMsg::Send {
-            to_address: energy_transfer.owner.clone(),
-            amount: coins(amount_to_transfer_to_owner.into(), &denom),
-        });
+    ensure_payouts_not_frozen(deps.as_ref())?;
+
+    if energy_transfer.status != TransferStatus::Ongoing {
+        return Err(ContractError::InvalidEnergyTransferStatus(TransferStatus::Ongoing, energy_transfer.status));
     }
 
+    let session_deadline = checked_plus_seconds(
+        energy_transfer.started_at.unwrap_or(energy_transfer.start_deadline),
+        energy_transfer.max_session_duration,
+    )?;
+    if env.block.time <= session_deadline {
+        return Err(ContractError::SessionNotTimedOut(energy_transfer_id, session_deadline));
+    }
 
-    energy_transfer.status = TransferStatus::Paid;
-    energy_transfer.paid_date = env.block.time;
-    energy_transfer.energy_transferred = used_service_units;
-    ENERGY_TRANSFERS.save(deps.storage, energy_transfer_id, &energy_transfer)?;
+    let config = CONFIG.load(deps.storage)?;
+    let amount_to_transfer_to_owner = match config.timeout_policy {
+        TimeoutPolicy::FullRefund => Uint128::zero(),
+        TimeoutPolicy::LastMeterReading => {
+            let energy = energy_transfer.energy_transferred.min(energy_transfer.energy_to_transfer);
+            amount_due(energy_transfer.offered_tariff, energy)?
+        }
+    };
+    let amount_to_transfer_to_payer = energy_transfer.collateral.checked_sub(amount_to_transfer_to_owner)?;
 
-    let mut offer = ENERGY_TRANSFER_OFFERS
-        .load(deps.storage, energy_transfer.energy_transfer_offer_id)
-        .map_err(|_| ContractError::EnergyOfferNotFound(energy_transfer.energy_transfer_offer_id))?;
+    let mut payouts: Vec<SubMsg> = vec![];
+    payouts.extend(refund_payer(deps.storage, &energy_transfer, amount_to_transfer_to_payer)?);
+    let (owner_payouts, fee_amount) = pay_owner(deps.storage, &config, energy_transfer.id, &energy_transfer.owner, amount_to_transfer_to_owner, &energy_transfer.asset)?;
+    payouts.extend(owner_payouts);
 
-    offer.charger_status = ChargerStatus::Active;
-    ENERGY_TRANSFER_OFFERS.save(deps.storage, energy_transfer.energy_transfer_offer_id, &offer)?;
+    energy_transfer.fee_amount = fee_amount;
+    energy_transfer.status = TransferStatus::TimedOut;
+    energy_transfer.paid_date = env.block.time;
+    energy_transfers().save(deps.storage, energy_transfer_id, &energy_transfer)?;
+
+    let mut offer = energy_transfer_offers().load(deps.storage, energy_transfer.energy_transfer_offer_id)?;
+    offer.charger_status = ChargerStatus::Faulted;
+    offer.current_energy_transfer_id = None;
+    energy_transfer_offers().save(deps.storage, energy_transfer.energy_transfer_offer_id, &offer)?;
 
     let events = vec![
-        Event::new("energy_transfer_completed")
+        Event::new("energy_transfer_timed_out")
             .add_attribute("energy_transfer_id", energy_transfer.id.to_string())
-            .add_attribute("energy_transferred", used_service_units.to_string()),
+            .add_attribute("charger_id", energy_transfer.charger_id)
+            .add_attribute("timeout_policy", config.timeout_policy.to_string())
+            .add_attribute("energy_transferred", energy_transfer.energy_transferred.to_string())
+            .add_attribute("refund", amount_to_transfer_to_payer.to_string())
+            .add_attribute("fee_amount", fee_amount.to_string()),
     ];
 
     Ok(Response::new()
         .add_events(events)
-        .add_messages(bank_messages)
+        .add_submessages(payouts)
     )
 }
-pub fn remove_energy_transfer(
+
+pub fn energy_transfer_completed(
     deps: DepsMut,
-    _env: Env,
-    _info: MessageInfo,
+    env: Env,
+    info: MessageInfo,
     energy_transfer_id: u64,
+    used_service_units: u64,
 ) -> Result<Response, ContractError> {
-    let energy_transfer = ENERGY_TRANSFERS
+    let mut energy_transfer = energy_transfers()
         .load(deps.storage, energy_transfer_id)
         .map_err(|_| ContractError::EnergyTransferNotFound(energy_transfer_id))?;
 
-    if energy_transfer.status != TransferStatus::Paid && energy_transfer.status != TransferStatus::Cancelled {
-        return Err(ContractError::InvalidEnergyTransferMultipleStatuses(TransferStatus::Paid, TransferStatus::Cancelled, energy_transfer.status));
+    ensure_connector(deps.as_ref(), &info.sender, &energy_transfer.owner)?;
+    ensure_payouts_not_frozen(deps.as_ref())?;
+
+    if energy_transfer.status != TransferStatus::Requested && energy_transfer.status != TransferStatus::Ongoing {
+        return Err(ContractError::InvalidEnergyTransferMultipleStatuses (TransferStatus::Requested, TransferStatus::Ongoing, energy_transfer.status));
     }
 
-    ENERGY_TRANSFERS.remove(deps.storage, energy_transfer_id);
+    if used_service_units < energy_transfer.energy_transferred {
+        return Err(ContractError::EnergyReadingDecreased(energy_transfer.energy_transferred, used_service_units));
+    }
 
-    let events = vec![
-        Event::new("remove_energy_transfer")
+    let (payouts, settlement_events) = settle_energy_transfer(deps.storage, &env, &mut energy_transfer, used_service_units)?;
+
+    let mut events = vec![
+        Event::new("energy_transfer_completed")
             .add_attribute("energy_transfer_id", energy_transfer.id.to_string())
-            .add_attribute("status", format!("{:?}", energy_transfer.status)),
+            .add_attribute("energy_transferred", used_service_units.to_string())
+            .add_attribute("fee_amount", energy_transfer.fee_amount.to_string()),
     ];
+    events.extend(settlement_events);
 
-    Ok(Response::new().add_events(events))
+    Ok(Response::new()
+        .add_events(events)
+        .add_submessages(payouts)
+    )
+}
+
+/// Records the cumulative meter reading of an ongoing session. The session is
+/// completed as soon as `energy_to_transfer` is reached, unless payouts are frozen
+/// in which case it stays open until the connector reports completion.
+pub fn report_energy_progress(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    energy_transfer_id: u64,
+    energy_transferred: u64,
+    timestamp: Timestamp,
+) -> Result<Response, ContractError> {
+    let mut energy_transfer = energy_transfers()
+        .load(deps.storage, energy_transfer_id)
+        .map_err(|_| ContractError::EnergyTransferNotFound(energy_transfer_id))?;
+
+    ensure_connector(deps.as_ref(), &info.sender, &energy_transfer.owner)?;
+
+    if energy_transfer.status != TransferStatus::Ongoing {
+        return Err(ContractError::InvalidEnergyTransferStatus(TransferStatus::Ongoing, energy_transfer.status));
+    }
+
+    if energy_transferred < energy_transfer.energy_transferred {
+        return Err(ContractError::EnergyReadingDecreased(energy_transfer.energy_transferred, energy_transferred));
+    }
+
+    if timestamp > env.block.time {
+        return Err(ContractError::ReadingInFuture(timestamp));
+    }
+    let previous_reading = energy_transfer.last_reported_at.or(energy_transfer.started_at);
+    if let Some(previous_reading) = previous_reading {
+        if timestamp <= previous_reading {
+            return Err(ContractError::ReadingOutOfOrder(previous_reading, timestamp));
+        }
+    }
+
+    energy_transfer.energy_transferred = energy_transferred;
+    energy_transfer.last_reported_at = Some(timestamp);
+
+    let mut event = Event::new("report_energy_progress")
+        .add_attribute("energy_transfer_id", energy_transfer.id.to_string())
+        .add_attribute("energy_transferred", energy_transferred.to_string())
+        .add_attribute("timestamp", timestamp.to_string());
+
+    let mut payouts: Vec<SubMsg> = vec![];
+    let mut settlement_events: Vec<Event> = vec![];
+    if energy_transferred >= energy_transfer.energy_to_transfer && !CONTRACT_STATUS.load(deps.storage)?.payouts_frozen {
+        (payouts, settlement_events) = settle_energy_transfer(deps.storage, &env, &mut energy_transfer, energy_transferred)?;
+        event = event
+            .add_attribute("status", energy_transfer.status.to_string())
+            .add_attribute("fee_amount", energy_transfer.fee_amount.to_string());
+    } else {
+        energy_transfers().save(deps.storage, energy_transfer_id, &energy_transfer)?;
+    }
+
+    Ok(Response::new()
+        .add_event(event)
+        .add_events(settlement_events)
+        .add_submessages(payouts)
+    )
 }
 
+/// Pays the owner for `used_service_units` and refunds the rest of the collateral to
+/// the payer. Energy beyond `energy_to_transfer` is handled by the session's
+/// `OverconsumptionPolicy`. The protocol fee is taken from the owner's share. Frees the charger.
+fn settle_energy_transfer(
+    storage: &mut dyn Storage,
+    env: &Env,
+    energy_transfer: &mut EnergyTransfer,
+    used_service_units: u64,
+) -> Result<(Vec<SubMsg>, Vec<Event>), ContractError> {
+    let mut payouts: Vec<SubMsg> = vec![];
+    let mut events: Vec<Event> = vec![];
+    let config = CONFIG.load(storage)?;
+
+    // The owner is paid for the energy covered by the collateral, the payer gets back the rest
+    let energy = used_service_units.min(energy_transfer.energy_to_transfer);
+    let amount_to_transfer_to_owner = amount_due(energy_transfer.offered_tariff, energy)?;
+    let amount_to_transfer_to_payer = energy_transfer.collateral.checked_sub(amount_to_transfer_to_owner)?;
+    payouts.extend(refund_payer(storage, energy_transfer, amount_to_transfer_to_payer)?);
+
+    let (owner_payouts, fee_amount) = pay_owner(storage, &config, energy_transfer.id, &energy_transfer.owner, amount_to_transfer_to_owner, &energy_transfer.asset)?;
+    payouts.extend(owner_payouts);
+
+    energy_transfer.fee_amount = fee_amount;
+    energy_transfer.status = TransferStatus::Paid;
+    energy_transfer.paid_date = env.block.time;
+    energy_transfer.energy_transferred = used_service_units;
+
+    if used_service_units > energy_transfer.energy_to_transfer {
+        let excess_energy = used_service_units - energy_transfer.energy_to_transfer;
+        energy_transfer.excess_energy = excess_energy;
+
+        let mut event = Event::new("energy_overconsumption")
+            .add_attribute("energy_transfer_id", energy_transfer.id.to_string())
+            .add_attribute("driver", energy_transfer.driver.clone())
+            .add_attribute("excess_energy", excess_energy.to_string())
+            .add_attribute("overconsumption_policy", energy_transfer.overconsumption_policy.to_string());
+
+        match energy_transfer.overconsumption_policy {
+            // Only the energy covered by the collateral is recorded as transferred
+            OverconsumptionPolicy::HardCap => {
+                energy_transfer.energy_transferred = energy_transfer.energy_to_transfer;
+            }
+            OverconsumptionPolicy::DriverDebt => {
+                let debt = Debt {
+                    energy_transfer_id: energy_transfer.id,
+                    driver: energy_transfer.driver.clone(),
+                    owner: energy_transfer.owner.clone(),
+                    amount: amount_due(energy_transfer.offered_tariff, excess_energy)?,
+                    asset: energy_transfer.asset.clone(),
+                };
+                let driver = Addr::unchecked(&energy_transfer.driver);
+                DEBTS.save(storage, (&driver, energy_transfer.id), &debt)?;
+                event = event.add_attribute("debt", debt.amount.to_string());
+            }
+        }
+        events.push(event);
+    }
+
+    energy_transfers().save(storage, energy_transfer.id, energy_transfer)?;
+
+    release_charger(storage, energy_transfer.energy_transfer_offer_id)?;
+
+    Ok((payouts, events))
+}
+
+pub fn remove_energy_transfer(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    energy_transfer_id: u64,
+) -> Result<Response, ContractError> {
+    let energy_transfer = energy_transfers()
+        .load(deps.storage, energy_transfer_id)
+        .map_err(|_| ContractError::EnergyTransferNotFound(energy_transfer_id))?;
+
+    // Settled history is kept for both parties, only infrastructure may prune it
+    if !is_connector(deps.as_ref(), &info.sender, &energy_transfer.owner)?
+        && !is_admin(deps.as_ref(), &info.sender)?
+    {
+        return Err(ContractError::UnauthorizedRemover(info.sender.to_string()));
+    }
+
+    if !matches!(energy_transfer.status, TransferStatus::Paid | TransferStatus::Cancelled | TransferStatus::Expired | TransferStatus::TimedOut) {
+        return Err(ContractError::EnergyTransferNotSettled(energy_transfer.status));
+    }
+
+    energy_transfers().remove(deps.storage, energy_transfer_id)?;
+
+    let events = vec![
+        Event::new("remove_energy_transfer")
+            .add_attribute("energy_transfer_id", energy_transfer.id.to_string())
+            .add_attribute("status", format!("{:?}", energy_transfer.status)),
+    ];
+
+    Ok(Response::new().add_events(events))
+}
+
+
+pub fn claim_payouts(
+    deps: DepsMut,
+    info: MessageInfo,
+    asset: Option<Asset>,
+) -> Result<Response, ContractError> {
+    ensure_payouts_not_frozen(deps.as_ref())?;
+
+    let asset = asset_or_native(deps.as_ref(), asset)?;
+    let asset_key = asset.to_string();
+    let amount = CLAIMABLE.may_load(deps.storage, (&info.sender, &asset_key))?.unwrap_or_default();
+    if amount.is_zero() {
+        return Err(ContractError::NothingToClaim);
+    }
+    CLAIMABLE.remove(deps.storage, (&info.sender, &asset_key));
+
+    let events = vec![
+        Event::new("claim_payouts")
+            .add_attribute("recipient", info.sender.to_string())
+            .add_attribute("amount", amount.to_string())
+            .add_attribute("asset", asset_key),
+    ];
+
+    Ok(Response::new()
+        .add_message(transfer_msg(&asset, info.sender.as_str(), amount)?)
+        .add_events(events)
+    )
+}
+
+/// Sends accrued earnings out of the contract. Only the sender's own `EARNINGS` are
+/// checked here; that the contract holds enough to cover every balance, deposit and
+/// open session is not checked at runtime but by `assert_solvent` in the tests.
+pub fn withdraw(
+    deps: DepsMut,
+    info: MessageInfo,
+    amount: Uint128,
+    recipient: Option<String>,
+    asset: Option<Asset>,
+) -> Result<Response, ContractError> {
+    ensure_payouts_not_frozen(deps.as_ref())?;
+
+    if amount.is_zero() {
+        return Err(ContractError::ZeroWithdrawal);
+    }
+    let recipient = match recipient {
+        Some(recipient) => deps.api.addr_validate(&recipient)?,
+        None => info.sender.clone(),
+    };
+
+    let asset = asset_or_native(deps.as_ref(), asset)?;
+    let asset_key = asset.to_string();
+    let available = EARNINGS.may_load(deps.storage, (&info.sender, &asset_key))?.unwrap_or_default();
+    if amount > available {
+        return Err(ContractError::InsufficientBalance(available, amount));
+    }
+    let remaining = available - amount;
+    if remaining.is_zero() {
+        EARNINGS.remove(deps.storage, (&info.sender, &asset_key));
+    } else {
+        EARNINGS.save(deps.storage, (&info.sender, &asset_key), &remaining)?;
+    }
+
+    let events = vec![
+        Event::new("withdraw")
+            .add_attribute("owner", info.sender.to_string())
+            .add_attribute("recipient", recipient.to_string())
+            .add_attribute("amount", amount.to_string())
+            .add_attribute("asset", asset_key)
+            .add_attribute("remaining", remaining.to_string()),
+    ];
+
+    Ok(Response::new()
+        .add_message(transfer_msg(&asset, recipient.as_str(), amount)?)
+        .add_events(events)
+    )
+}
+
+pub fn deposit(
+    deps: DepsMut,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    ensure_not_paused(deps.as_ref())?;
+
+    let denom = CONFIG.load(deps.storage)?.denom;
+    let amount = must_pay(&info, &denom)?;
+
+    let mut deposit = DEPOSITS.may_load(deps.storage, &info.sender)?.unwrap_or_default();
+    deposit.free = deposit.free.checked_add(amount)?;
+    DEPOSITS.save(deps.storage, &info.sender, &deposit)?;
+
+    let events = vec![
+        Event::new("deposit")
+            .add_attribute("driver", info.sender.to_string())
+            .add_attribute("amount", amount.to_string())
+            .add_attribute("free", deposit.free.to_string()),
+    ];
+
+    Ok(Response::new().add_events(events))
+}
+
+pub fn withdraw_deposit(
+    deps: DepsMut,
+    info: MessageInfo,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    ensure_payouts_not_frozen(deps.as_ref())?;
+
+    if amount.is_zero() {
+        return Err(ContractError::ZeroWithdrawal);
+    }
+
+    let mut deposit = DEPOSITS.may_load(deps.storage, &info.sender)?.unwrap_or_default();
+    if amount > deposit.free {
+        return Err(ContractError::InsufficientBalance(deposit.free, amount));
+    }
+    deposit.free -= amount;
+    if deposit == Deposit::default() {
+        DEPOSITS.remove(deps.storage, &info.sender);
+    } else {
+        DEPOSITS.save(deps.storage, &info.sender, &deposit)?;
+    }
+
+    let denom = CONFIG.load(deps.storage)?.denom;
+    let bank_msg = BankMsg::Send {
+        to_address: info.sender.to_string(),
+        amount: coins(amount.u128(), &denom),
+    };
+
+    let events = vec![
+        Event::new("withdraw_deposit")
+            .add_attribute("driver", info.sender.to_string())
+            .add_attribute("amount", amount.to_string())
+            .add_attribute("free", deposit.free.to_string()),
+    ];
+
+    Ok(Response::new()
+        .add_message(bank_msg)
+        .add_events(events)
+    )
+}
+
+pub fn approve_deposit_connector(
+    deps: DepsMut,
+    info: MessageInfo,
+    connector: String,
+) -> Result<Response, ContractError> {
+    let connector_addr = deps.api.addr_validate(&connector)?;
+    if !CONNECTORS.has(deps.storage, &connector_addr) {
+        return Err(ContractError::ConnectorNotFound(connector));
+    }
+    DEPOSIT_CONNECTORS.save(deps.storage, (&info.sender, &connector_addr), &Empty {})?;
+
+    let events = vec![
+        Event::new("approve_deposit_connector")
+            .add_attribute("driver", info.sender.to_string())
+            .add_attribute("connector", connector_addr.to_string()),
+    ];
+
+    Ok(Response::new().add_events(events))
+}
+
+pub fn revoke_deposit_connector(
+    deps: DepsMut,
+    info: MessageInfo,
+    connector: String,
+) -> Result<Response, ContractError> {
+    let connector_addr = deps.api.addr_validate(&connector)?;
+    if !DEPOSIT_CONNECTORS.has(deps.storage, (&info.sender, &connector_addr)) {
+        return Err(ContractError::ConnectorNotFound(connector));
+    }
+    DEPOSIT_CONNECTORS.remove(deps.storage, (&info.sender, &connector_addr));
+
+    let events = vec![
+        Event::new("revoke_deposit_connector")
+            .add_attribute("driver", info.sender.to_string())
+            .add_attribute("connector", connector_addr.to_string()),
+    ];
+
+    Ok(Response::new().add_events(events))
+}
+
+pub fn add_connector(
+    deps: DepsMut,
+    info: MessageInfo,
+    address: String,
+    owner: Option<String>,
+) -> Result<Response, ContractError> {
+    ensure_admin(deps.as_ref(), &info.sender)?;
+
+    let connector_addr = deps.api.addr_validate(&address)?;
+    if let Some(owner) = &owner {
+        deps.api.addr_validate(owner)?;
+    }
+
+    let connector = Connector {
+        address: connector_addr.to_string(),
+        owner: owner.clone(),
+    };
+    CONNECTORS.save(deps.storage, &connector_addr, &connector)?;
+
+    let mut event = Event::new("add_connector")
+        .add_attribute("connector", connector_addr.to_string());
+    if let Some(owner) = owner {
+        event = event.add_attribute("owner", owner);
+    }
+
+    Ok(Response::new().add_event(event))
+}
+
+pub fn remove_connector(
+    deps: DepsMut,
+    info: MessageInfo,
+    address: String,
+) -> Result<Response, ContractError> {
+    ensure_admin(deps.as_ref(), &info.sender)?;
+
+    let connector_addr = deps.api.addr_validate(&address)?;
+    if !CONNECTORS.has(deps.storage, &connector_addr) {
+        return Err(ContractError::ConnectorNotFound(address));
+    }
+
+    CONNECTORS.remove(deps.storage, &connector_addr);
+
+    let events = vec![
+        Event::new("remove_connector")
+            .add_attribute("connector", connector_addr.to_string()),
+    ];
+
+    Ok(Response::new().add_events(events))
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn update_config(
+    deps: DepsMut,
+    info: MessageInfo,
+    fee_bps: Option<u64>,
+    treasury: Option<String>,
+    limits: Option<Limits>,
+    start_timeout: Option<u64>,
+    timeout_policy: Option<TimeoutPolicy>,
+    payout_mode: Option<PayoutMode>,
+    accepted_denoms: Option<Vec<String>>,
+    cw20_tokens: Option<Vec<String>>,
+    oracle: Option<String>,
+    max_price_age: Option<u64>,
+) -> Result<Response, ContractError> {
+    ensure_admin(deps.as_ref(), &info.sender)?;
+
+    let mut config = CONFIG.load(deps.storage)?;
+    let mut event = Event::new("update_config");
+
+    if let Some(fee_bps) = fee_bps {
+        config.fee_bps = fee_bps;
+        event = event.add_attribute("fee_bps", fee_bps.to_string());
+    }
+    if let Some(treasury) = treasury {
+        let treasury = deps.api.addr_validate(&treasury)?;
+        event = event.add_attribute("treasury", treasury.to_string());
+        config.treasury = Some(treasury);
+    }
+    if let Some(limits) = limits {
+        validate_limits(&limits)?;
+        event = event.add_attribute("limits", format!("{:?}", limits));
+        config.limits = limits;
+    }
+    if let Some(start_timeout) = start_timeout {
+        validate_start_timeout(start_timeout)?;
+        config.start_timeout = start_timeout;
+        event = event.add_attribute("start_timeout", start_timeout.to_string());
+    }
+    if let Some(timeout_policy) = timeout_policy {
+        event = event.add_attribute("timeout_policy", timeout_policy.to_string());
+        config.timeout_policy = timeout_policy;
+    }
+    if let Some(payout_mode) = payout_mode {
+        event = event.add_attribute("payout_mode", payout_mode.to_string());
+        config.payout_mode = payout_mode;
+    }
+    // Offers keep their prices in assets dropped from the whitelist, sessions can no longer pay in them
+    if let Some(accepted_denoms) = accepted_denoms {
+        event = event.add_attribute("accepted_denoms", format!("[{}]", accepted_denoms.join(",")));
+        config.accepted_denoms = accepted_denoms;
+    }
+    if let Some(cw20_tokens) = cw20_tokens {
+        event = event.add_attribute("cw20_tokens", format!("[{}]", cw20_tokens.join(",")));
+        config.cw20_tokens = validate_cw20_tokens(deps.as_ref(), cw20_tokens)?;
+    }
+    if let Some(oracle) = oracle {
+        let oracle = deps.api.addr_validate(&oracle)?;
+        event = event.add_attribute("oracle", oracle.to_string());
+        config.oracle = Some(oracle);
+    }
+    if let Some(max_price_age) = max_price_age {
+        validate_max_price_age(max_price_age)?;
+        config.max_price_age = max_price_age;
+        event = event.add_attribute("max_price_age", max_price_age.to_string());
+    }
+    validate_fee(config.fee_bps, &config.treasury)?;
+
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new().add_event(event))
+}
+
+pub fn propose_admin(
+    deps: DepsMut,
+    info: MessageInfo,
+    new_admin: String,
+) -> Result<Response, ContractError> {
+    ensure_admin(deps.as_ref(), &info.sender)?;
+
+    let new_admin = deps.api.addr_validate(&new_admin)?;
+    PENDING_ADMIN.save(deps.storage, &new_admin)?;
+
+    let events = vec![
+        Event::new("propose_admin")
+            .add_attribute("admin", info.sender.to_string())
+            .add_attribute("pending_admin", new_admin.to_string()),
+    ];
+
+    Ok(Response::new().add_events(events))
+}
+
+pub fn accept_admin(
+    deps: DepsMut,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let pending_admin = PENDING_ADMIN
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NoPendingAdmin)?;
+
+    if pending_admin != info.sender {
+        return Err(ContractError::UnauthorizedPendingAdmin(info.sender.to_string()));
+    }
+
+    let mut config = CONFIG.load(deps.storage)?;
+    let previous_admin = config.admin;
+    config.admin = pending_admin;
+    CONFIG.save(deps.storage, &config)?;
+    PENDING_ADMIN.remove(deps.storage);
+
+    let events = vec![
+        Event::new("accept_admin")
+            .add_attribute("previous_admin", previous_admin.to_string())
+            .add_attribute("admin", config.admin.to_string()),
+    ];
+
+    Ok(Response::new().add_events(events))
+}
+
+pub fn set_contract_status(
+    deps: DepsMut,
+    info: MessageInfo,
+    paused: Option<bool>,
+    payouts_frozen: Option<bool>,
+) -> Result<Response, ContractError> {
+    ensure_admin(deps.as_ref(), &info.sender)?;
+
+    let previous = CONTRACT_STATUS.load(deps.storage)?;
+    let status = ContractStatus {
+        paused: paused.unwrap_or(previous.paused),
+        payouts_frozen: payouts_frozen.unwrap_or(previous.payouts_frozen),
+    };
+    CONTRACT_STATUS.save(deps.storage, &status)?;
+
+    let mut response = Response::new();
+    if status != previous {
+        response = response.add_event(
+            Event::new("contract_status_changed")
+                .add_attribute("paused", status.paused.to_string())
+                .add_attribute("payouts_frozen", status.payouts_frozen.to_string())
+                .add_attribute("admin", info.sender.to_string()),
+        );
+    }
+
+    Ok(response)
+}
+
+pub fn pay_debt(
+    deps: DepsMut,
+    info: MessageInfo,
+    energy_transfer_id: u64,
+) -> Result<Response, ContractError> {
+    let funds = native_funds(&info)?;
+    repay_debt(deps, info.sender, energy_transfer_id, funds)
+}
+
+fn repay_debt(
+    deps: DepsMut,
+    sender: Addr,
+    energy_transfer_id: u64,
+    funds: Funds,
+) -> Result<Response, ContractError> {
+    ensure_payouts_not_frozen(deps.as_ref())?;
+
+    let debt = DEBTS
+        .may_load(deps.storage, (&sender, energy_transfer_id))?
+        .ok_or(ContractError::DebtNotFound(energy_transfer_id))?;
+
+    let config = CONFIG.load(deps.storage)?;
+    let excess = collect_payment(&funds, &debt.asset, debt.amount)?;
+
+    DEBTS.remove(deps.storage, (&sender, energy_transfer_id));
+    let (mut payouts, fee_amount) = pay_owner(deps.storage, &config, energy_transfer_id, &debt.owner, debt.amount, &debt.asset)?;
+    payouts.extend(payout(deps.storage, energy_transfer_id, sender.as_str(), excess, &funds.asset)?);
+
+    // The transfer may have been pruned since it was settled
+    if let Some(mut energy_transfer) = energy_transfers().may_load(deps.storage, energy_transfer_id)? {
+        energy_transfer.fee_amount = energy_transfer.fee_amount.checked_add(fee_amount)?;
+        energy_transfers().save(deps.storage, energy_transfer_id, &energy_transfer)?;
+    }
+
+    let events = vec![
+        Event::new("pay_debt")
+            .add_attribute("energy_transfer_id", energy_transfer_id.to_string())
+            .add_attribute("driver", debt.driver)
+            .add_attribute("owner", debt.owner)
+            .add_attribute("amount", debt.amount.to_string())
+            .add_attribute("fee_amount", fee_amount.to_string()),
+    ];
+
+    Ok(Response::new()
+        .add_submessages(payouts)
+        .add_events(events)
+    )
+}
+
+/// Handles a cw20 `Send`, the sending token has to be one of the accepted ones.
+pub fn receive_cw20(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    wrapper: Cw20ReceiveMsg,
+) -> Result<Response, ContractError> {
+    let asset = Asset::Cw20(info.sender);
+    ensure_accepted(&CONFIG.load(deps.storage)?, &asset)?;
+
+    let sender = deps.api.addr_validate(&wrapper.sender)?;
+    let funds = Funds { asset, amount: wrapper.amount };
+    match from_json(&wrapper.msg)? {
+        ReceiveMsg::StartEnergyTransfer { driver, energy_transfer_offer_id, energy_to_transfer, max_tariff } => {
+            start(deps, env, sender, driver, energy_transfer_offer_id, energy_to_transfer, max_tariff, Funding::Received(funds))
+        }
+        ReceiveMsg::ExtendEnergyTransfer { energy_transfer_id, additional_energy } => {
+            extend(deps, sender, energy_transfer_id, additional_energy, Some(funds))
+        }
+        ReceiveMsg::PayDebt { energy_transfer_id } => repay_debt(deps, sender, energy_transfer_id, funds),
+    }
+}
+
+/// The single coin attached to the message, checked against the expected asset by the caller.
+fn native_funds(info: &MessageInfo) -> Result<Funds, ContractError> {
+    let funds = one_coin(info)?;
+    Ok(Funds { amount: funds.amount, asset: Asset::Native(funds.denom) })
+}
+
+/// Checks that `funds` are in `expected_asset` and cover `expected`, and returns
+/// the excess to refund.
+fn collect_payment(funds: &Funds, expected_asset: &Asset, expected: Uint128) -> Result<Uint128, ContractError> {
+    if funds.asset != *expected_asset || funds.amount < expected {
+        return Err(ContractError::InvalidFunds {
+            expected: coin(expected.u128(), expected_asset.to_string()),
+            received: coin(funds.amount.u128(), funds.asset.to_string()),
+        });
+    }
+    Ok(funds.amount - expected)
+}
+
+pub(crate) fn validate_cw20_tokens(deps: Deps, cw20_tokens: Vec<String>) -> StdResult<Vec<Addr>> {
+    cw20_tokens
+        .iter()
+        .map(|token| deps.api.addr_validate(token))
+        .collect()
+}
+
+pub(crate) fn validate_fee(fee_bps: u64, treasury: &Option<Addr>) -> Result<(), ContractError> {
+    if fee_bps > MAX_FEE_BPS {
+        return Err(ContractError::FeeTooHigh(MAX_FEE_BPS, fee_bps));
+    }
+    if fee_bps > 0 && treasury.is_none() {
+        return Err(ContractError::MissingTreasury);
+    }
+    Ok(())
+}
+
+pub(crate) fn validate_max_price_age(max_price_age: u64) -> Result<(), ContractError> {
+    if max_price_age == 0 {
+        return Err(ContractError::ZeroPriceAge);
+    }
+    if max_price_age > MAX_PRICE_AGE {
+        return Err(ContractError::PriceAgeLimitExceeded(MAX_PRICE_AGE, max_price_age));
+    }
+    Ok(())
+}
+
+pub(crate) fn validate_start_timeout(start_timeout: u64) -> Result<(), ContractError> {
+    if start_timeout == 0 {
+        return Err(ContractError::ZeroStartTimeout);
+    }
+    if start_timeout > MAX_START_TIMEOUT {
+        return Err(ContractError::StartTimeoutLimitExceeded(MAX_START_TIMEOUT, start_timeout));
+    }
+    Ok(())
+}
+
+pub(crate) fn validate_limits(limits: &Limits) -> Result<(), ContractError> {
+    match limits.max_session_duration {
+        Some(0) => Err(ContractError::ZeroSessionDuration),
+        Some(max_session_duration) if max_session_duration > MAX_SESSION_DURATION => {
+            Err(ContractError::SessionDurationLimitExceeded(MAX_SESSION_DURATION, max_session_duration))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn validate_session_duration(config: &Config, max_session_duration: u64) -> Result<(), ContractError> {
+    if max_session_duration == 0 {
+        return Err(ContractError::ZeroSessionDuration);
+    }
+    let limit = config.limits.max_session_duration.unwrap_or(MAX_SESSION_DURATION);
+    if max_session_duration > limit {
+        return Err(ContractError::SessionDurationLimitExceeded(limit, max_session_duration));
+    }
+    Ok(())
+}
+
+/// `time` moved `seconds` ahead, failing instead of panicking on overflow.
+pub(crate) fn checked_plus_seconds(time: Timestamp, seconds: u64) -> Result<Timestamp, OverflowError> {
+    seconds
+        .checked_mul(1_000_000_000)
+        .and_then(|nanos| time.nanos().checked_add(nanos))
+        .map(Timestamp::from_nanos)
+        .ok_or_else(|| OverflowError::new(OverflowOperation::Add, time.nanos(), seconds))
+}
+
+/// Ends the offer's current session. A charger reported Faulted during the session stays Faulted.
+fn release_charger(storage: &mut dyn Storage, energy_transfer_offer_id: u64) -> Result<(), ContractError> {
+    let mut offer = energy_transfer_offers()
+        .load(storage, energy_transfer_offer_id)
+        .map_err(|_| ContractError::EnergyOfferNotFound(energy_transfer_offer_id))?;
+
+    if offer.charger_status == ChargerStatus::Busy {
+        offer.charger_status = ChargerStatus::Active;
+    }
+    offer.current_energy_transfer_id = None;
+    energy_transfer_offers().save(storage, energy_transfer_offer_id, &offer)?;
+    Ok(())
+}
+
+/// Protocol fee on `amount`, rounded down so that the owner and the treasury
+/// together receive exactly `amount`. Cannot overflow as `fee_bps` is capped below 100%.
+pub(crate) fn protocol_fee(fee_bps: u64, amount: Uint128) -> Uint128 {
+    amount.multiply_ratio(fee_bps, 10_000u64)
+}
+
+/// Pays `amount` to the owner less the protocol fee, which goes to the treasury.
+/// In `PayoutMode::Pull` the owner's share is credited to its earnings instead.
+/// Returns the payouts and the fee charged.
+fn pay_owner(
+    storage: &mut dyn Storage,
+    config: &Config,
+    energy_transfer_id: u64,
+    owner: &str,
+    amount: Uint128,
+    asset: &Asset,
+) -> Result<(Vec<SubMsg>, Uint128), ContractError> {
+    let fee_amount = match &config.treasury {
+        Some(_) => protocol_fee(config.fee_bps, amount),
+        None => Uint128::zero(),
+    };
+
+    let owner_amount = amount.checked_sub(fee_amount)?;
+    let mut payouts: Vec<SubMsg> = vec![];
+    match config.payout_mode {
+        PayoutMode::Push => payouts.extend(payout(storage, energy_transfer_id, owner, owner_amount, asset)?),
+        PayoutMode::Pull => {
+            EARNINGS.update(storage, (&Addr::unchecked(owner), &asset.to_string()), |earnings| -> Result<_, ContractError> {
+                Ok(earnings.unwrap_or_default().checked_add(owner_amount)?)
+            })?;
+        }
+    }
+    if let Some(treasury) = &config.treasury {
+        payouts.extend(payout(storage, energy_transfer_id, treasury.as_str(), fee_amount, asset)?);
+    }
+
+    if !fee_amount.is_zero() {
+        FEES_COLLECTED.update(storage, &asset.to_string(), |collected| -> Result<_, ContractError> {
+            Ok(collected.unwrap_or_default().checked_add(fee_amount)?)
+        })?;
+    }
+
+    Ok((payouts, fee_amount))
+}
+
+/// Moves `amount` of the driver's free deposit to its locked balance.
+fn lock_deposit(storage: &mut dyn Storage, driver: &Addr, amount: Uint128) -> Result<(), ContractError> {
+    let mut deposit = DEPOSITS.may_load(storage, driver)?.unwrap_or_default();
+    if amount > deposit.free {
+        return Err(ContractError::InsufficientBalance(deposit.free, amount));
+    }
+    deposit.free -= amount;
+    deposit.locked = deposit.locked.checked_add(amount)?;
+    DEPOSITS.save(storage, driver, &deposit)?;
+    Ok(())
+}
+
+/// Returns `amount` of the collateral to the payer. Collateral locked from a deposit
+/// is unlocked as a whole, with `amount` going back to the free balance.
+fn refund_payer(
+    storage: &mut dyn Storage,
+    energy_transfer: &EnergyTransfer,
+    amount: Uint128,
+) -> Result<Option<SubMsg>, ContractError> {
+    if !energy_transfer.from_deposit {
+        return Ok(payout(storage, energy_transfer.id, &energy_transfer.payer, amount, &energy_transfer.asset)?);
+    }
+
+    let payer = Addr::unchecked(&energy_transfer.payer);
+    let mut deposit = DEPOSITS.load(storage, &payer)?;
+    deposit.locked = deposit.locked.checked_sub(energy_transfer.collateral)?;
+    deposit.free = deposit.free.checked_add(amount)?;
+    DEPOSITS.save(storage, &payer, &deposit)?;
+    Ok(None)
+}
+
+/// Sends back what was paid over the collateral. Like any other payout this is held
+/// back while payouts are frozen, so overpaying is rejected then.
+fn refund_excess(
+    storage: &mut dyn Storage,
+    energy_transfer_id: u64,
+    recipient: &str,
+    excess: Uint128,
+    asset: &Asset,
+) -> Result<Option<SubMsg>, ContractError> {
+    if !excess.is_zero() && CONTRACT_STATUS.load(storage)?.payouts_frozen {
+        return Err(ContractError::PayoutsFrozen);
+    }
+    Ok(payout(storage, energy_transfer_id, recipient, excess, asset)?)
+}
+
+/// Sends a payout as a submessage replying always, so that a recipient refusing
+/// funds is credited a claimable balance instead of reverting the whole settlement.
+/// The payout record is dropped on either reply. Zero amounts produce no message.
+fn payout(
+    storage: &mut dyn Storage,
+    energy_transfer_id: u64,
+    recipient: &str,
+    amount: Uint128,
+    asset: &Asset,
+) -> StdResult<Option<SubMsg>> {
+    if amount.is_zero() {
+        return Ok(None);
+    }
+
+    let reply_id = PAYOUT_COUNT.may_load(storage)?.unwrap_or_default() + 1;
+    PAYOUT_COUNT.save(storage, &reply_id)?;
+    PAYOUTS.save(storage, reply_id, &Payout {
+        energy_transfer_id,
+        recipient: recipient.to_string(),
+        amount,
+        asset: asset.clone(),
+    })?;
+
+    Ok(Some(SubMsg::reply_always(transfer_msg(asset, recipient, amount)?, reply_id)))
+}
+
+/// Bank send for native assets, cw20 `Transfer` for tokens.
+fn transfer_msg(asset: &Asset, recipient: &str, amount: Uint128) -> StdResult<CosmosMsg> {
+    let msg = match asset {
+        Asset::Native(denom) => BankMsg::Send {
+            to_address: recipient.to_string(),
+            amount: coins(amount.u128(), denom),
+        }.into(),
+        Asset::Cw20(token) => WasmMsg::Execute {
+            contract_addr: token.to_string(),
+            msg: to_json_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: recipient.to_string(),
+                amount,
+            })?,
+            funds: vec![],
+        }.into(),
+    };
+    Ok(msg)
+}
+
+fn ensure_not_paused(deps: Deps) -> Result<(), ContractError> {
+    if CONTRACT_STATUS.load(deps.storage)?.paused {
+        return Err(ContractError::ContractPaused);
+    }
+    Ok(())
+}
+
+fn ensure_payouts_not_frozen(deps: Deps) -> Result<(), ContractError> {
+    if CONTRACT_STATUS.load(deps.storage)?.payouts_frozen {
+        return Err(ContractError::PayoutsFrozen);
+    }
+    Ok(())
+}
+
+fn is_admin(deps: Deps, sender: &Addr) -> StdResult<bool> {
+    Ok(CONFIG.load(deps.storage)?.admin == *sender)
+}
+
+fn ensure_admin(deps: Deps, sender: &Addr) -> Result<(), ContractError> {
+    if !is_admin(deps, sender)? {
+        return Err(ContractError::UnauthorizedAdmin(sender.to_string()));
+    }
+    Ok(())
+}
+
+/// A connector is authorized for a transfer when it is registered network-wide
+/// or scoped to the owner of the offer the transfer was started on.
+fn is_connector(deps: Deps, sender: &Addr, owner: &str) -> StdResult<bool> {
+    Ok(match CONNECTORS.may_load(deps.storage, sender)? {
+        Some(Connector { owner: None, .. }) => true,
+        Some(Connector { owner: Some(scope), .. }) => scope == owner,
+        None => false,
+    })
+}
+
+fn ensure_connector(deps: Deps, sender: &Addr, owner: &str) -> Result<(), ContractError> {
+    if !is_connector(deps, sender, owner)? {
+        return Err(ContractError::UnauthorizedConnector(sender.to_string()));
+    }
+    Ok(())
+}
@@ -4,5 +4,7 @@ pub mod msg;
 pub mod state;
 pub mod query;
 pub mod execute;
+mod migrations;
+pub mod reply;
 
 pub use crate::error::ContractError;
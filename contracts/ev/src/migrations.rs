@@ -0,0 +1,160 @@
+use std::collections::HashMap;
+use cosmwasm_std::{Decimal, DepsMut, Env, Order, StdResult, Uint128};
+use semver::Version;
+use crate::ContractError;
+use crate::execute::{DEFAULT_MAX_PRICE_AGE, DEFAULT_MAX_SESSION_DURATION, DEFAULT_START_TIMEOUT};
+use crate::msg::{Asset, Config, ContractStatus, EnergyTransfer, EnergyTransferOffer, Limits, MigrateMsg, OverconsumptionPolicy, PayoutMode, TimeoutPolicy, TransferStatus};
+use crate::state::{energy_transfer_offers, energy_transfers, CONFIG, CONTRACT_STATUS};
+
+type MigrationStep = fn(DepsMut, &Env, &MigrateMsg) -> Result<(), ContractError>;
+
+/// Upgrade steps in ascending order, each tagged with the version it migrates to.
+/// A step runs when the stored version is older than its tag.
+const MIGRATIONS: &[(&str, MigrationStep)] = &[
+    ("0.2.0", migrate_to_v0_2_0),
+];
+
+/// Runs every step newer than `from` and returns the versions that were applied.
+pub(crate) fn run(mut deps: DepsMut, env: &Env, from: &Version, msg: &MigrateMsg) -> Result<Vec<String>, ContractError> {
+    let mut applied = vec![];
+    for (version, step) in MIGRATIONS {
+        let target = Version::parse(version).map_err(|err| ContractError::SemVer(err.to_string()))?;
+        if *from < target {
+            step(deps.branch(), env, msg)?;
+            applied.push(version.to_string());
+        }
+    }
+    Ok(applied)
+}
+
+/// Storage layout of the 0.1.x releases.
+pub(crate) mod v0_1 {
+    use cosmwasm_schema::cw_serde;
+    use cosmwasm_std::Timestamp;
+    use cw_storage_plus::{Item, Map};
+    use crate::msg::{ChargerStatus, Location, PlugType, TransferStatus};
+
+    pub const DENOM: Item<String> = Item::new("denom");
+    pub const ENERGY_TRANSFER_OFFERS: Map<u64, EnergyTransferOffer> = Map::new("energy_transfer_offers");
+    pub const ENERGY_TRANSFERS: Map<u64, EnergyTransfer> = Map::new("energy_transfers");
+
+    #[cw_serde]
+    pub struct EnergyTransferOffer {
+        pub id: u64,
+        pub owner: String,
+        pub charger_id: String,
+        pub charger_status: ChargerStatus,
+        pub location: Location,
+        pub tariff: u64,
+        pub name: String,
+        pub plug_type: PlugType,
+    }
+
+    #[cw_serde]
+    pub struct EnergyTransfer {
+        pub id: u64,
+        pub energy_transfer_offer_id: u64,
+        pub charger_id: String,
+        pub owner: String,
+        pub driver: String,
+        pub offered_tariff: u64,
+        pub status: TransferStatus,
+        pub collateral: u64,
+        pub energy_to_transfer: u64,
+        pub energy_transferred: u64,
+        pub paid_date: Timestamp,
+    }
+}
+
+/// 0.1.x kept the denom as the only global setting and had no admin,
+/// so the admin has to be supplied with the migrate message.
+/// Offers and transfers are rewritten in place with amounts widened to `Uint128`
+/// and tariffs converted to `Decimal`.
+/// The records are passed as new (`old_data` of `None`) since the stored ones do
+/// not parse as the current layout, which also builds their secondary indexes.
+fn migrate_to_v0_2_0(deps: DepsMut, env: &Env, msg: &MigrateMsg) -> Result<(), ContractError> {
+    let admin = msg.admin.as_ref().ok_or(ContractError::MissingMigrationAdmin)?;
+    let config = Config {
+        admin: deps.api.addr_validate(admin)?,
+        denom: v0_1::DENOM.load(deps.storage)?,
+        fee_bps: 0,
+        treasury: None,
+        limits: Limits::default(),
+        start_timeout: DEFAULT_START_TIMEOUT,
+        timeout_policy: TimeoutPolicy::default(),
+        payout_mode: PayoutMode::default(),
+        accepted_denoms: vec![],
+        cw20_tokens: vec![],
+        oracle: None,
+        max_price_age: DEFAULT_MAX_PRICE_AGE,
+    };
+    CONFIG.save(deps.storage, &config)?;
+    CONTRACT_STATUS.save(deps.storage, &ContractStatus::default())?;
+    v0_1::DENOM.remove(deps.storage);
+
+    let transfers = v0_1::ENERGY_TRANSFERS
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    // Sessions still holding a charger, to link them back to their offer
+    let open_sessions: HashMap<u64, u64> = transfers
+        .iter()
+        .filter(|(_, transfer)| matches!(transfer.status, TransferStatus::Requested | TransferStatus::Ongoing))
+        .map(|(id, transfer)| (transfer.energy_transfer_offer_id, *id))
+        .collect();
+
+    let offers = v0_1::ENERGY_TRANSFER_OFFERS
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for (id, offer) in offers {
+        let offer = EnergyTransferOffer {
+            id: offer.id,
+            owner: offer.owner,
+            charger_id: offer.charger_id,
+            charger_status: offer.charger_status,
+            location: offer.location,
+            tariff: Decimal::from_ratio(offer.tariff, 1u64),
+            name: offer.name,
+            plug_type: offer.plug_type,
+            revision: 0,
+            current_energy_transfer_id: open_sessions.get(&id).copied(),
+            max_session_duration: DEFAULT_MAX_SESSION_DURATION,
+            overconsumption_policy: OverconsumptionPolicy::default(),
+            prices: vec![],
+            fiat_tariff: None,
+        };
+        energy_transfer_offers().replace(deps.storage, id, Some(&offer), None)?;
+    }
+
+    for (id, transfer) in transfers {
+        let started_at = (transfer.status == TransferStatus::Ongoing).then_some(env.block.time);
+        let transfer = EnergyTransfer {
+            id: transfer.id,
+            energy_transfer_offer_id: transfer.energy_transfer_offer_id,
+            charger_id: transfer.charger_id,
+            owner: transfer.owner,
+            // 0.1.x refunded unused collateral to the driver, so the driver stays the payer
+            payer: transfer.driver.clone(),
+            driver: transfer.driver,
+            offered_tariff: Decimal::from_ratio(transfer.offered_tariff, 1u64),
+            status: transfer.status,
+            collateral: Uint128::from(transfer.collateral),
+            energy_to_transfer: transfer.energy_to_transfer,
+            energy_transferred: transfer.energy_transferred,
+            paid_date: transfer.paid_date,
+            // Open sessions get their full start and session timeouts from the upgrade on
+            start_deadline: env.block.time.plus_seconds(DEFAULT_START_TIMEOUT),
+            started_at,
+            last_reported_at: None,
+            max_session_duration: DEFAULT_MAX_SESSION_DURATION,
+            overconsumption_policy: OverconsumptionPolicy::default(),
+            excess_energy: 0,
+            fee_amount: Uint128::zero(),
+            from_deposit: false,
+            asset: Asset::Native(config.denom.clone()),
+        };
+        energy_transfers().replace(deps.storage, id, Some(&transfer), None)?;
+    }
+
+    Ok(())
+}
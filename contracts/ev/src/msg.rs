@@ -1,12 +1,30 @@
 use std::fmt;
 use cosmwasm_schema::{cw_serde, QueryResponses};
-use cosmwasm_std::Timestamp;
+use cosmwasm_std::{Addr, Decimal, Timestamp, Uint128};
+use cw20::Cw20ReceiveMsg;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 #[cw_serde]
 pub struct InstantiateMsg {
     pub denom: String,
+    /// Defaults to the instantiating address.
+    pub admin: Option<String>,
+    pub fee_bps: Option<u64>,
+    pub treasury: Option<String>,
+    pub limits: Option<Limits>,
+    /// Seconds a requested session has to be started. Defaults to `DEFAULT_START_TIMEOUT`.
+    pub start_timeout: Option<u64>,
+    pub timeout_policy: Option<TimeoutPolicy>,
+    pub payout_mode: Option<PayoutMode>,
+    /// Native denoms accepted for payment besides `denom`.
+    pub accepted_denoms: Option<Vec<String>>,
+    /// cw20 tokens accepted for payment besides `denom`.
+    pub cw20_tokens: Option<Vec<String>>,
+    /// Price oracle converting fiat tariffs, see `OracleQueryMsg`.
+    pub oracle: Option<String>,
+    /// Seconds an oracle price stays usable. Defaults to `DEFAULT_MAX_PRICE_AGE`.
+    pub max_price_age: Option<u64>,
 }
 
 #[cw_serde]
@@ -14,22 +32,229 @@ pub enum ExecuteMsg {
     PublishEnergyTransferOffer {
         charger_id: String,
         location: Location,
-        tariff: u64,
+        tariff: Decimal,
         name: String,
         plug_type: PlugType,
+        /// Seconds a session may run once started, up to the configured limit.
+        /// Defaults to `DEFAULT_MAX_SESSION_DURATION`.
+        max_session_duration: Option<u64>,
+        overconsumption_policy: Option<OverconsumptionPolicy>,
+        /// Prices in other accepted assets, `tariff` being the price in the native denom.
+        prices: Option<Vec<Price>>,
+        fiat_tariff: Option<FiatTariff>,
     },
     RemoveEnergyOffer {
         energy_offer_id: u64
     },
+    /// Changes the given fields of an offer, unset fields are left as they are.
+    UpdateEnergyTransferOffer {
+        energy_transfer_offer_id: u64,
+        tariff: Option<Decimal>,
+        name: Option<String>,
+        location: Option<Location>,
+        plug_type: Option<PlugType>,
+        max_session_duration: Option<u64>,
+        overconsumption_policy: Option<OverconsumptionPolicy>,
+        /// Replaces the offer's prices in other assets as a whole.
+        prices: Option<Vec<Price>>,
+        /// Prices the offer in fiat through the configured oracle.
+        fiat_tariff: Option<FiatTariff>,
+        /// Removes the fiat tariff so the offer is priced in tokens again.
+        /// Cannot be combined with `fiat_tariff`.
+        clear_fiat_tariff: Option<bool>,
+    },
+    /// Sent by the offer owner or its connector to take a charger offline or bring it back.
+    SetChargerStatus {
+        energy_transfer_offer_id: u64,
+        status: ChargerStatus,
+    },
+    /// The sender funds the collateral and is recorded as the session's payer,
+    /// who may be someone other than `driver`. With `from_deposit` the collateral is
+    /// locked from the driver's deposit instead, and the driver or a connector of the
+    /// offer's owner approved by the driver may send it without funds.
+    /// `denom` picks which of the offer's prices is paid and defaults to the native denom.
+    /// The request fails if the offered tariff is above `max_tariff`, which bounds the
+    /// slippage of fiat tariffs converted at the oracle rate.
     StartEnergyTransfer {
         driver: String,
         energy_transfer_offer_id: u64,
         energy_to_transfer: u64,
+        from_deposit: Option<bool>,
+        denom: Option<String>,
+        max_tariff: Option<Decimal>,
     },
     EnergyTransferStarted { energy_transfer_id: u64 },
+    /// Sent by the payer with `additional_energy` times the session's `offered_tariff`, rounded up,
+    /// to raise the energy of an ongoing session. Sessions started from a deposit lock
+    /// the top-up from it instead.
+    ExtendEnergyTransfer { energy_transfer_id: u64, additional_energy: u64 },
     EnergyTransferCompleted { energy_transfer_id: u64, used_service_units: u64 },
+    /// Cumulative meter reading of an ongoing session, taken at `timestamp`.
+    ReportEnergyProgress { energy_transfer_id: u64, energy_transferred: u64, timestamp: Timestamp },
     CancelEnergyTransfer { energy_transfer_id: u64 },
+    /// Refunds a session that was not started before its deadline. Anyone may send it.
+    ExpireEnergyTransfer { energy_transfer_id: u64 },
+    /// Sent by the driver or the payer to settle a started session that outlived the offer's
+    /// `max_session_duration` without a completion report.
+    ClaimTimedOutEnergyTransfer { energy_transfer_id: u64 },
     RemoveEnergyTransfer { energy_transfer_id: u64 },
+    /// Registers a connector allowed to report session lifecycle events.
+    /// When `owner` is set the connector may only act on transfers of that offer owner.
+    AddConnector { address: String, owner: Option<String> },
+    RemoveConnector { address: String },
+    /// Updates the given config fields. `limits` replaces the current limits as a whole.
+    UpdateConfig {
+        fee_bps: Option<u64>,
+        treasury: Option<String>,
+        limits: Option<Limits>,
+        start_timeout: Option<u64>,
+        timeout_policy: Option<TimeoutPolicy>,
+        payout_mode: Option<PayoutMode>,
+        accepted_denoms: Option<Vec<String>>,
+        cw20_tokens: Option<Vec<String>>,
+        oracle: Option<String>,
+        max_price_age: Option<u64>,
+    },
+    /// First step of the admin handover, must be accepted by `new_admin`.
+    ProposeAdmin { new_admin: String },
+    AcceptAdmin {},
+    /// Emergency switches. Unset fields keep their current value.
+    SetContractStatus { paused: Option<bool>, payouts_frozen: Option<bool> },
+    /// Withdraws the sender's balance of payouts in `asset` that could not be delivered.
+    /// Defaults to the native denom.
+    ClaimPayouts { asset: Option<Asset> },
+    /// Settles the driver's debt from an overconsumed session, funds must match the debt.
+    PayDebt { energy_transfer_id: u64 },
+    /// Sends `amount` of the sender's accrued earnings in `asset` to `recipient`, or to the
+    /// sender if unset. The asset defaults to the native denom.
+    Withdraw { amount: Uint128, recipient: Option<String>, asset: Option<Asset> },
+    /// Adds the attached funds to the sender's deposit.
+    Deposit {},
+    /// Sends `amount` of the sender's free deposit back to it.
+    WithdrawDeposit { amount: Uint128 },
+    /// Allows `connector` to start sessions from the sender's deposit.
+    ApproveDepositConnector { connector: String },
+    RevokeDepositConnector { connector: String },
+    /// Payment in one of the accepted cw20 tokens, carrying a `ReceiveMsg`.
+    Receive(Cw20ReceiveMsg),
+}
+
+/// Messages embedded in a cw20 `Send` to this contract. The sent tokens fund them
+/// as attached funds do for their native counterparts.
+#[cw_serde]
+pub enum ReceiveMsg {
+    StartEnergyTransfer {
+        driver: String,
+        energy_transfer_offer_id: u64,
+        energy_to_transfer: u64,
+        max_tariff: Option<Decimal>,
+    },
+    ExtendEnergyTransfer { energy_transfer_id: u64, additional_energy: u64 },
+    PayDebt { energy_transfer_id: u64 },
+}
+
+/// Token a session is paid in.
+#[cw_serde]
+pub enum Asset {
+    Native(String),
+    Cw20(Addr),
+}
+
+/// The denom or token address, also used to key per asset balances.
+impl fmt::Display for Asset {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Asset::Native(denom) => write!(f, "{}", denom),
+            Asset::Cw20(address) => write!(f, "{}", address),
+        }
+    }
+}
+
+#[cw_serde]
+pub struct Config {
+    pub admin: Addr,
+    pub denom: String,
+    /// Protocol fee taken from the owner's share of a settlement, in basis points.
+    pub fee_bps: u64,
+    pub treasury: Option<Addr>,
+    pub limits: Limits,
+    /// Seconds a requested session has to be started before it can be expired,
+    /// at most `MAX_START_TIMEOUT`.
+    pub start_timeout: u64,
+    pub timeout_policy: TimeoutPolicy,
+    pub payout_mode: PayoutMode,
+    /// Native denoms accepted for payment besides `denom`.
+    pub accepted_denoms: Vec<String>,
+    pub cw20_tokens: Vec<Addr>,
+    pub oracle: Option<Addr>,
+    /// Seconds an oracle price stays usable, at most `MAX_PRICE_AGE`.
+    pub max_price_age: u64,
+}
+
+/// How a session claimed back by the driver after timing out is settled.
+#[cw_serde]
+#[derive(Default)]
+pub enum TimeoutPolicy {
+    /// The driver gets the whole collateral back.
+    #[default]
+    FullRefund,
+    /// The owner is paid for `energy_transferred` as last reported, the rest is refunded.
+    LastMeterReading,
+}
+
+impl fmt::Display for TimeoutPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TimeoutPolicy::FullRefund => write!(f, "FullRefund"),
+            TimeoutPolicy::LastMeterReading => write!(f, "LastMeterReading"),
+        }
+    }
+}
+
+/// How owners receive their share of a settlement.
+#[cw_serde]
+#[derive(Default)]
+pub enum PayoutMode {
+    /// Sent to the owner with every settlement.
+    #[default]
+    Push,
+    /// Credited to the owner's earnings, to be collected with `Withdraw`.
+    Pull,
+}
+
+impl fmt::Display for PayoutMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PayoutMode::Push => write!(f, "Push"),
+            PayoutMode::Pull => write!(f, "Pull"),
+        }
+    }
+}
+
+/// `paused` stops new offers and sessions while in-flight sessions can still be
+/// settled. `payouts_frozen` additionally stops every message that releases funds,
+/// including refunds of overpaid collateral.
+#[cw_serde]
+#[derive(Default)]
+pub struct ContractStatus {
+    pub paused: bool,
+    pub payouts_frozen: bool,
+}
+
+#[cw_serde]
+#[derive(Default)]
+pub struct Limits {
+    pub max_energy_to_transfer: Option<u64>,
+    pub max_tariff: Option<Decimal>,
+    /// Longest `max_session_duration` an offer may set, in seconds. Defaults to and
+    /// may not exceed `MAX_SESSION_DURATION`.
+    pub max_session_duration: Option<u64>,
+}
+
+#[cw_serde]
+pub struct Connector {
+    pub address: String,
+    pub owner: Option<String>,
 }
 
 #[cw_serde]
@@ -39,9 +264,91 @@ pub struct EnergyTransferOffer {
     pub charger_id: String,
     pub charger_status: ChargerStatus,
     pub location: Location,
-    pub tariff: u64,
+    /// Price per service unit in the native denom. The collateral of a session is rounded
+    /// up to whole tokens, the owner's share is rounded down and the payer gets the rest.
+    pub tariff: Decimal,
     pub name: String,
     pub plug_type: PlugType,
+    /// Number of updates applied since the offer was published.
+    pub revision: u64,
+    /// Session currently holding the charger.
+    pub current_energy_transfer_id: Option<u64>,
+    /// Seconds a session may run once started.
+    pub max_session_duration: u64,
+    pub overconsumption_policy: OverconsumptionPolicy,
+    /// Prices in other accepted assets, `tariff` being the price in the native denom.
+    pub prices: Vec<Price>,
+    /// When set, sessions are priced from it at the oracle rate of the paid asset
+    /// instead of `tariff` and `prices`.
+    pub fiat_tariff: Option<FiatTariff>,
+}
+
+/// Tariff of an offer in a given asset.
+#[cw_serde]
+pub struct Price {
+    pub asset: Asset,
+    pub tariff: Decimal,
+}
+
+/// Tariff in the minor unit of a fiat currency, e.g. EUR cents.
+#[cw_serde]
+pub struct FiatTariff {
+    pub currency: String,
+    pub amount: Decimal,
+}
+
+/// Query interface expected from the price oracle.
+#[cw_serde]
+#[derive(QueryResponses)]
+pub enum OracleQueryMsg {
+    /// Rate of `asset`, a denom or cw20 address, against `currency`.
+    #[returns(OraclePriceResponse)]
+    Price { currency: String, asset: String },
+}
+
+#[cw_serde]
+pub struct OraclePriceResponse {
+    /// Amount of the asset in its smallest unit per minor unit of the currency.
+    pub rate: Decimal,
+    pub updated_at: Timestamp,
+}
+
+/// What happens when a session is settled with more energy than `energy_to_transfer`.
+#[cw_serde]
+#[derive(Default)]
+pub enum OverconsumptionPolicy {
+    /// The owner is paid up to the collateral, the excess is recorded on the transfer.
+    #[default]
+    HardCap,
+    /// The excess is owed by the driver, who cannot start new sessions until it is paid.
+    DriverDebt,
+}
+
+impl fmt::Display for OverconsumptionPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OverconsumptionPolicy::HardCap => write!(f, "HardCap"),
+            OverconsumptionPolicy::DriverDebt => write!(f, "DriverDebt"),
+        }
+    }
+}
+
+#[cw_serde]
+pub struct Debt {
+    pub energy_transfer_id: u64,
+    pub driver: String,
+    pub owner: String,
+    pub amount: Uint128,
+    /// Asset of the session, the debt has to be paid in.
+    pub asset: Asset,
+}
+
+#[cw_serde]
+pub struct Payout {
+    pub energy_transfer_id: u64,
+    pub recipient: String,
+    pub amount: Uint128,
+    pub asset: Asset,
 }
 
 #[cw_serde]
@@ -55,6 +362,8 @@ pub enum ChargerStatus {
     Active,
     Busy,
     Inactive,
+    Maintenance,
+    Faulted,
     Unspecified,
 }
 
@@ -64,6 +373,8 @@ impl fmt::Display for ChargerStatus {
             ChargerStatus::Active => write!(f, "Active"),
             ChargerStatus::Busy => write!(f, "Busy"),
             ChargerStatus::Inactive => write!(f, "Inactive"),
+            ChargerStatus::Maintenance => write!(f, "Maintenance"),
+            ChargerStatus::Faulted => write!(f, "Faulted"),
             ChargerStatus::Unspecified => write!(f, "Unspecified"),
         }
     }
@@ -85,12 +396,32 @@ pub struct EnergyTransfer {
     pub charger_id: String,
     pub owner: String,
     pub driver: String,
-    pub offered_tariff: u64,
+    /// Sender of `StartEnergyTransfer`, who funded the collateral and gets back what is unused.
+    pub payer: String,
+    /// The offer's price in `asset` when the session was requested.
+    pub offered_tariff: Decimal,
     pub status: TransferStatus,
-    pub collateral: u64,
+    pub collateral: Uint128,
     pub energy_to_transfer: u64,
     pub energy_transferred: u64,
     pub paid_date: Timestamp,
+    /// A session still `Requested` after this time can be expired.
+    pub start_deadline: Timestamp,
+    pub started_at: Option<Timestamp>,
+    /// Time of the last meter reading behind `energy_transferred`.
+    pub last_reported_at: Option<Timestamp>,
+    /// The offer's `max_session_duration` when the session was requested.
+    pub max_session_duration: u64,
+    /// The offer's `overconsumption_policy` when the session was requested.
+    pub overconsumption_policy: OverconsumptionPolicy,
+    /// Energy reported beyond `energy_to_transfer` at settlement.
+    pub excess_energy: u64,
+    /// Protocol fee taken from the owner's share and sent to the treasury.
+    pub fee_amount: Uint128,
+    /// The collateral is locked in the driver's deposit rather than attached to the request.
+    pub from_deposit: bool,
+    /// Asset the collateral was paid in, settlement and refunds are paid in it too.
+    pub asset: Asset,
 }
 
 #[derive(Debug, PartialEq, Serialize, Deserialize, Clone, JsonSchema)]
@@ -99,6 +430,8 @@ pub enum TransferStatus {
     Ongoing,
     Paid,
     Cancelled,
+    Expired,
+    TimedOut,
     Unspecified,
 }
 
@@ -109,13 +442,18 @@ impl fmt::Display for TransferStatus {
             TransferStatus::Ongoing => write!(f, "Ongoing"),
             TransferStatus::Paid => write!(f, "Paid"),
             TransferStatus::Cancelled => write!(f, "Cancelled"),
+            TransferStatus::Expired => write!(f, "Expired"),
+            TransferStatus::TimedOut => write!(f, "TimedOut"),
             TransferStatus::Unspecified => write!(f, "Unspecified"),
         }
     }
 }
 
 #[cw_serde]
-pub enum MigrateMsg {}
+pub struct MigrateMsg {
+    /// Admin to install when migrating from a release that had none.
+    pub admin: Option<String>,
+}
 
 #[cw_serde]
 #[derive(QueryResponses)]
@@ -125,17 +463,59 @@ pub enum QueryMsg {
     #[returns(EnergyTransferOfferResponse)]
     EnergyTransferOffer { id: u64 },
     #[returns(AllEnergyTransferOffersResponse)]
-    AllEnergyTransferOffers {},
+    AllEnergyTransferOffers { start_after: Option<u64>, limit: Option<u32> },
     #[returns(EnergyTransferResponse)]
     EnergyTransfer { id: u64 },
+    #[returns(EnergyTransferProgressResponse)]
+    EnergyTransferProgress { id: u64 },
     #[returns(AllEnergyTransfersResponse)]
-    AllEnergyTransfers {},
+    AllEnergyTransfers { start_after: Option<u64>, limit: Option<u32> },
     #[returns(EnergyTransferOffersByOwnerResponse)]
-    EnergyTransferOffers { owner: String },
+    EnergyTransferOffers { owner: String, start_after: Option<u64>, limit: Option<u32> },
     #[returns(OwnEnergyTransfersResponse)]
-    OwnEnergyTransfers { driver: String, transfer_status: TransferStatus},
+    OwnEnergyTransfers { driver: String, transfer_status: TransferStatus, start_after: Option<u64>, limit: Option<u32> },
     #[returns(EnergyTransfersByOwnerResponse)]
-    EnergyTransfers { owner: String, },
+    EnergyTransfers { owner: String, start_after: Option<u64>, limit: Option<u32> },
+    #[returns(EnergyTransfersByPayerResponse)]
+    PayerEnergyTransfers { payer: String, start_after: Option<u64>, limit: Option<u32> },
+    #[returns(EnergyTransferOffersByChargerResponse)]
+    ChargerEnergyTransferOffers { charger_id: String, start_after: Option<u64>, limit: Option<u32> },
+    #[returns(EnergyTransfersByChargerResponse)]
+    ChargerEnergyTransfers { charger_id: String, start_after: Option<u64>, limit: Option<u32> },
+    #[returns(EnergyTransfersByStatusResponse)]
+    EnergyTransfersByStatus { transfer_status: TransferStatus, start_after: Option<u64>, limit: Option<u32> },
+    #[returns(ConnectorsResponse)]
+    Connectors { start_after: Option<String>, limit: Option<u32> },
+    #[returns(ConfigResponse)]
+    Config {},
+    #[returns(ContractStatusResponse)]
+    ContractStatus {},
+    /// Balances are kept per asset, `asset` defaults to the native denom.
+    #[returns(ClaimableResponse)]
+    Claimable { address: String, asset: Option<Asset> },
+    #[returns(DebtsResponse)]
+    Debts { driver: String, start_after: Option<u64>, limit: Option<u32> },
+    #[returns(FeeInfoResponse)]
+    FeeInfo { asset: Option<Asset> },
+    /// Earnings in `asset` accrued to `address` and not withdrawn yet.
+    #[returns(BalanceResponse)]
+    Balance { address: String, asset: Option<Asset> },
+    #[returns(DepositResponse)]
+    Deposit { address: String },
+    #[returns(DepositConnectorsResponse)]
+    DepositConnectors { address: String, start_after: Option<String>, limit: Option<u32> },
+}
+
+#[cw_serde]
+pub struct EnergyTransferProgressResponse {
+    pub energy_transfer_id: u64,
+    pub status: TransferStatus,
+    pub energy_to_transfer: u64,
+    pub energy_transferred: u64,
+    /// Amount owed to the owner for `energy_transferred` so far.
+    pub amount_due: Uint128,
+    pub started_at: Option<Timestamp>,
+    pub last_reported_at: Option<Timestamp>,
 }
 
 #[cw_serde]
@@ -170,3 +550,82 @@ pub struct OwnEnergyTransfersResponse {
 pub struct EnergyTransfersByOwnerResponse {
     pub energy_transfers: Vec<EnergyTransfer>,
 }
+#[cw_serde]
+pub struct EnergyTransfersByPayerResponse {
+    pub energy_transfers: Vec<EnergyTransfer>,
+}
+#[cw_serde]
+pub struct EnergyTransferOffersByChargerResponse {
+    pub energy_transfer_offers: Vec<EnergyTransferOffer>,
+}
+#[cw_serde]
+pub struct EnergyTransfersByChargerResponse {
+    pub energy_transfers: Vec<EnergyTransfer>,
+}
+#[cw_serde]
+pub struct EnergyTransfersByStatusResponse {
+    pub energy_transfers: Vec<EnergyTransfer>,
+}
+#[cw_serde]
+pub struct ConnectorsResponse {
+    pub connectors: Vec<Connector>,
+}
+#[cw_serde]
+pub struct ConfigResponse {
+    pub config: Config,
+    pub pending_admin: Option<Addr>,
+}
+#[cw_serde]
+pub struct ContractStatusResponse {
+    pub status: ContractStatus,
+}
+#[cw_serde]
+pub struct ClaimableResponse {
+    pub amount: Uint128,
+}
+
+#[cw_serde]
+pub struct BalanceResponse {
+    pub amount: Uint128,
+}
+
+/// A driver's prepaid balance. `locked` backs the collateral of its open sessions.
+#[cw_serde]
+#[derive(Default)]
+pub struct Deposit {
+    pub free: Uint128,
+    pub locked: Uint128,
+}
+
+#[cw_serde]
+pub struct DepositResponse {
+    pub free: Uint128,
+    pub locked: Uint128,
+}
+#[cw_serde]
+pub struct DepositConnectorsResponse {
+    /// Connectors allowed to start sessions from the deposit.
+    pub connectors: Vec<Addr>,
+}
+
+#[cw_serde]
+pub struct FeeInfoResponse {
+    pub fee_bps: u64,
+    pub max_fee_bps: u64,
+    pub treasury: Option<Addr>,
+    /// Sum of all fees charged in the queried asset since the fee was introduced.
+    pub total_collected: Uint128,
+}
+
+#[cw_serde]
+pub struct DebtsResponse {
+    pub debts: Vec<Debt>,
+    /// Sum of all the driver's debts per asset, not only the returned page.
+    pub total: Vec<AssetAmount>,
+}
+
+#[cw_serde]
+pub struct AssetAmount {
+    pub asset: Asset,
+    pub amount: Uint128,
+}
@@ -1,25 +1,46 @@
-use cosmwasm_std::{Deps, StdResult};
-use crate::msg::{AllEnergyTransferOffersResponse, AllEnergyTransfersResponse, DenomResponse, EnergyTransfer, EnergyTransferOffer, EnergyTransferOfferResponse, EnergyTransferOffersByOwnerResponse, EnergyTransferResponse, EnergyTransfersByOwnerResponse, OwnEnergyTransfersResponse, TransferStatus};
+use cosmwasm_std::{Addr, Deps, Order, StdResult};
+use cw_storage_plus::Bound;
+use crate::execute::{amount_due, MAX_FEE_BPS};
+use crate::msg::{AllEnergyTransferOffersResponse, AllEnergyTransfersResponse, Asset, AssetAmount, BalanceResponse, ClaimableResponse, ConfigResponse, ConnectorsResponse, ContractStatusResponse, DebtsResponse, DenomResponse, DepositConnectorsResponse, DepositResponse, EnergyTransfer, EnergyTransferOffer, EnergyTransferOfferResponse, EnergyTransferOffersByChargerResponse, EnergyTransferOffersByOwnerResponse, EnergyTransferProgressResponse, EnergyTransferResponse, EnergyTransfersByChargerResponse, EnergyTransfersByOwnerResponse, EnergyTransfersByPayerResponse, EnergyTransfersByStatusResponse, FeeInfoResponse, OwnEnergyTransfersResponse, TransferStatus};
 
-use crate::state::{DENOM, ENERGY_TRANSFER_OFFERS, ENERGY_TRANSFERS};
+use crate::state::{energy_transfer_offers, energy_transfers, CLAIMABLE, CONFIG, CONNECTORS, CONTRACT_STATUS, DEBTS, DEPOSIT_CONNECTORS, DEPOSITS, EARNINGS, FEES_COLLECTED, PENDING_ADMIN};
+
+pub const DEFAULT_LIMIT: u32 = 10;
+pub const MAX_LIMIT: u32 = 30;
 
 pub fn query_denom(deps: Deps) -> StdResult<DenomResponse> {
     let resp = DenomResponse {
-        denom: DENOM.load(deps.storage)?,
+        denom: CONFIG.load(deps.storage)?.denom,
+    };
+    Ok(resp)
+}
+
+pub fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
+    let resp = ConfigResponse {
+        config: CONFIG.load(deps.storage)?,
+        pending_admin: PENDING_ADMIN.may_load(deps.storage)?,
+    };
+    Ok(resp)
+}
+
+pub fn query_contract_status(deps: Deps) -> StdResult<ContractStatusResponse> {
+    let resp = ContractStatusResponse {
+        status: CONTRACT_STATUS.load(deps.storage)?,
     };
     Ok(resp)
 }
 
 pub fn query_energy_transfer_offer(deps: Deps, id: u64) -> StdResult<EnergyTransferOfferResponse> {
-    let offer = ENERGY_TRANSFER_OFFERS.load(deps.storage, id)?;
+    let offer = energy_transfer_offers().load(deps.storage, id)?;
     let resp = EnergyTransferOfferResponse {
         energy_transfer_offer: offer,
     };
     Ok(resp)
 }
 
-pub fn query_all_energy_transfer_offers(deps: Deps) -> StdResult<AllEnergyTransferOffersResponse> {
-    let offers = get_energy_transfer_offers(deps)
+pub fn query_all_energy_transfer_offers(deps: Deps, start_after: Option<u64>, limit: Option<u32>) -> StdResult<AllEnergyTransferOffersResponse> {
+    let offers = get_energy_transfer_offers(deps, start_after)
+        .take(page_size(limit))
         .map(|item| item.map(|(_, v)| v))
         .collect::<StdResult<Vec<_>>>()?;
 
@@ -30,15 +51,30 @@ pub fn query_all_energy_transfer_offers(deps: Deps) -> StdResult<AllEnergyTransf
 }
 
 pub fn query_energy_transfer(deps: Deps, id: u64) -> StdResult<EnergyTransferResponse> {
-    let transfer = ENERGY_TRANSFERS.load(deps.storage, id)?;
+    let transfer = energy_transfers().load(deps.storage, id)?;
     let resp = EnergyTransferResponse {
         energy_transfer: transfer,
     };
     Ok(resp)
 }
 
-pub fn query_all_energy_transfers(deps: Deps) -> StdResult<AllEnergyTransfersResponse> {
-    let transfers = get_energy_transfers(deps)
+pub fn query_energy_transfer_progress(deps: Deps, id: u64) -> StdResult<EnergyTransferProgressResponse> {
+    let transfer = energy_transfers().load(deps.storage, id)?;
+    let resp = EnergyTransferProgressResponse {
+        energy_transfer_id: transfer.id,
+        status: transfer.status,
+        energy_to_transfer: transfer.energy_to_transfer,
+        energy_transferred: transfer.energy_transferred,
+        amount_due: amount_due(transfer.offered_tariff, transfer.energy_transferred.min(transfer.energy_to_transfer))?,
+        started_at: transfer.started_at,
+        last_reported_at: transfer.last_reported_at,
+    };
+    Ok(resp)
+}
+
+pub fn query_all_energy_transfers(deps: Deps, start_after: Option<u64>, limit: Option<u32>) -> StdResult<AllEnergyTransfersResponse> {
+    let transfers = get_energy_transfers(deps, start_after)
+        .take(page_size(limit))
         .map(|item| item.map(|(_, v)| v))
         .collect::<StdResult<Vec<_>>>()?;
     let resp = AllEnergyTransfersResponse {
@@ -47,10 +83,14 @@ pub fn query_all_energy_transfers(deps: Deps) -> StdResult<AllEnergyTransfersRes
     Ok(resp)
 }
 
-pub fn query_energy_transfer_offers(deps: Deps, owner: String) -> StdResult<EnergyTransferOffersByOwnerResponse> {
-    let offers = get_energy_transfer_offers(deps)
+pub fn query_energy_transfer_offers(deps: Deps, owner: String, start_after: Option<u64>, limit: Option<u32>) -> StdResult<EnergyTransferOffersByOwnerResponse> {
+    let offers = energy_transfer_offers()
+        .idx
+        .owner
+        .prefix(owner)
+        .range(deps.storage, start_after.map(Bound::exclusive), None, Order::Ascending)
+        .take(page_size(limit))
         .map(|item| item.map(|(_, v)| v))
-        .filter(|offer| offer.as_ref().unwrap().owner == owner)
         .collect::<StdResult<Vec<_>>>()?;
     let resp = EnergyTransferOffersByOwnerResponse {
         energy_transfer_offers: offers,
@@ -58,10 +98,20 @@ pub fn query_energy_transfer_offers(deps: Deps, owner: String) -> StdResult<Ener
     Ok(resp)
 }
 
-pub fn query_own_energy_transfers(deps: Deps, driver: String, transfer_status: TransferStatus) -> StdResult<OwnEnergyTransfersResponse> {
-    let transfers = get_energy_transfers(deps)
+pub fn query_own_energy_transfers(
+    deps: Deps,
+    driver: String,
+    transfer_status: TransferStatus,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<OwnEnergyTransfersResponse> {
+    let transfers = energy_transfers()
+        .idx
+        .driver
+        .prefix((driver, transfer_status.to_string()))
+        .range(deps.storage, start_after.map(Bound::exclusive), None, Order::Ascending)
+        .take(page_size(limit))
         .map(|item| item.map(|(_, v)| v))
-        .filter(|transfer| transfer.as_ref().unwrap().driver == driver && transfer.as_ref().unwrap().status == transfer_status)
         .collect::<StdResult<Vec<_>>>()?;
     let resp = OwnEnergyTransfersResponse {
         energy_transfers: transfers,
@@ -69,10 +119,14 @@ pub fn query_own_energy_transfers(deps: Deps, driver: String, transfer_status: T
     Ok(resp)
 }
 
-pub fn query_energy_transfers(deps: Deps, owner: String) -> StdResult<EnergyTransfersByOwnerResponse> {
-    let transfers = get_energy_transfers(deps)
+pub fn query_energy_transfers(deps: Deps, owner: String, start_after: Option<u64>, limit: Option<u32>) -> StdResult<EnergyTransfersByOwnerResponse> {
+    let transfers = energy_transfers()
+        .idx
+        .owner
+        .prefix(owner)
+        .range(deps.storage, start_after.map(Bound::exclusive), None, Order::Ascending)
+        .take(page_size(limit))
         .map(|item| item.map(|(_, v)| v))
-        .filter(|transfer| transfer.as_ref().unwrap().owner == owner)
         .collect::<StdResult<Vec<_>>>()?;
     let resp = EnergyTransfersByOwnerResponse {
         energy_transfers: transfers,
@@ -80,10 +134,193 @@ pub fn query_energy_transfers(deps: Deps, owner: String) -> StdResult<EnergyTran
     Ok(resp)
 }
 
-pub fn get_energy_transfers(deps: Deps<'_>) -> Box<dyn Iterator<Item=StdResult<(u64, EnergyTransfer)>> + '_> {
-    return ENERGY_TRANSFERS.range(deps.storage, None, None, cosmwasm_std::Order::Ascending);
+pub fn query_payer_energy_transfers(deps: Deps, payer: String, start_after: Option<u64>, limit: Option<u32>) -> StdResult<EnergyTransfersByPayerResponse> {
+    let transfers = energy_transfers()
+        .idx
+        .payer
+        .prefix(payer)
+        .range(deps.storage, start_after.map(Bound::exclusive), None, Order::Ascending)
+        .take(page_size(limit))
+        .map(|item| item.map(|(_, v)| v))
+        .collect::<StdResult<Vec<_>>>()?;
+    let resp = EnergyTransfersByPayerResponse {
+        energy_transfers: transfers,
+    };
+    Ok(resp)
+}
+
+pub fn query_charger_energy_transfer_offers(deps: Deps, charger_id: String, start_after: Option<u64>, limit: Option<u32>) -> StdResult<EnergyTransferOffersByChargerResponse> {
+    let offers = energy_transfer_offers()
+        .idx
+        .charger_id
+        .prefix(charger_id)
+        .range(deps.storage, start_after.map(Bound::exclusive), None, Order::Ascending)
+        .take(page_size(limit))
+        .map(|item| item.map(|(_, v)| v))
+        .collect::<StdResult<Vec<_>>>()?;
+    let resp = EnergyTransferOffersByChargerResponse {
+        energy_transfer_offers: offers,
+    };
+    Ok(resp)
+}
+
+pub fn query_charger_energy_transfers(deps: Deps, charger_id: String, start_after: Option<u64>, limit: Option<u32>) -> StdResult<EnergyTransfersByChargerResponse> {
+    let transfers = energy_transfers()
+        .idx
+        .charger_id
+        .prefix(charger_id)
+        .range(deps.storage, start_after.map(Bound::exclusive), None, Order::Ascending)
+        .take(page_size(limit))
+        .map(|item| item.map(|(_, v)| v))
+        .collect::<StdResult<Vec<_>>>()?;
+    let resp = EnergyTransfersByChargerResponse {
+        energy_transfers: transfers,
+    };
+    Ok(resp)
+}
+
+pub fn query_energy_transfers_by_status(
+    deps: Deps,
+    transfer_status: TransferStatus,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<EnergyTransfersByStatusResponse> {
+    let transfers = energy_transfers()
+        .idx
+        .status
+        .prefix(transfer_status.to_string())
+        .range(deps.storage, start_after.map(Bound::exclusive), None, Order::Ascending)
+        .take(page_size(limit))
+        .map(|item| item.map(|(_, v)| v))
+        .collect::<StdResult<Vec<_>>>()?;
+    let resp = EnergyTransfersByStatusResponse {
+        energy_transfers: transfers,
+    };
+    Ok(resp)
+}
+
+pub fn query_connectors(deps: Deps, start_after: Option<String>, limit: Option<u32>) -> StdResult<ConnectorsResponse> {
+    let start_after = start_after.map(|address| deps.api.addr_validate(&address)).transpose()?;
+    let connectors = CONNECTORS
+        .range(deps.storage, start_after.as_ref().map(Bound::exclusive), None, Order::Ascending)
+        .take(page_size(limit))
+        .map(|item| item.map(|(_, v)| v))
+        .collect::<StdResult<Vec<_>>>()?;
+    let resp = ConnectorsResponse {
+        connectors,
+    };
+    Ok(resp)
+}
+
+pub fn query_balance(deps: Deps, address: String, asset: Option<Asset>) -> StdResult<BalanceResponse> {
+    let address = deps.api.addr_validate(&address)?;
+    let asset = asset_or_native(deps, asset)?;
+    let resp = BalanceResponse {
+        amount: EARNINGS.may_load(deps.storage, (&address, &asset.to_string()))?.unwrap_or_default(),
+    };
+    Ok(resp)
+}
+
+pub fn query_deposit(deps: Deps, address: String) -> StdResult<DepositResponse> {
+    let address = deps.api.addr_validate(&address)?;
+    let deposit = DEPOSITS.may_load(deps.storage, &address)?.unwrap_or_default();
+    let resp = DepositResponse {
+        free: deposit.free,
+        locked: deposit.locked,
+    };
+    Ok(resp)
+}
+
+pub fn query_deposit_connectors(deps: Deps, address: String, start_after: Option<String>, limit: Option<u32>) -> StdResult<DepositConnectorsResponse> {
+    let address = deps.api.addr_validate(&address)?;
+    let start_after = start_after.map(|connector| deps.api.addr_validate(&connector)).transpose()?;
+    let connectors = DEPOSIT_CONNECTORS
+        .prefix(&address)
+        .keys(deps.storage, start_after.as_ref().map(Bound::exclusive), None, Order::Ascending)
+        .take(page_size(limit))
+        .collect::<StdResult<Vec<_>>>()?;
+    let resp = DepositConnectorsResponse {
+        connectors,
+    };
+    Ok(resp)
+}
+
+pub fn query_fee_info(deps: Deps, asset: Option<Asset>) -> StdResult<FeeInfoResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let asset = asset_or_native(deps, asset)?;
+    let resp = FeeInfoResponse {
+        fee_bps: config.fee_bps,
+        max_fee_bps: MAX_FEE_BPS,
+        treasury: config.treasury,
+        total_collected: FEES_COLLECTED.may_load(deps.storage, &asset.to_string())?.unwrap_or_default(),
+    };
+    Ok(resp)
+}
+
+pub fn query_claimable(deps: Deps, address: String, asset: Option<Asset>) -> StdResult<ClaimableResponse> {
+    let address = deps.api.addr_validate(&address)?;
+    let asset = asset_or_native(deps, asset)?;
+    let resp = ClaimableResponse {
+        amount: CLAIMABLE.may_load(deps.storage, (&address, &asset.to_string()))?.unwrap_or_default(),
+    };
+    Ok(resp)
+}
+
+pub fn query_debts(deps: Deps, driver: String, start_after: Option<u64>, limit: Option<u32>) -> StdResult<DebtsResponse> {
+    let driver = deps.api.addr_validate(&driver)?;
+    let debts = DEBTS
+        .prefix(&driver)
+        .range(deps.storage, start_after.map(Bound::exclusive), None, Order::Ascending)
+        .take(page_size(limit))
+        .map(|item| item.map(|(_, v)| v))
+        .collect::<StdResult<Vec<_>>>()?;
+    let total = total_debts(deps, &driver)?;
+    let resp = DebtsResponse {
+        debts,
+        total,
+    };
+    Ok(resp)
+}
+
+/// Sums a driver's debts per asset, in the order each asset is first owed.
+fn total_debts(deps: Deps, driver: &Addr) -> StdResult<Vec<AssetAmount>> {
+    let mut totals: Vec<AssetAmount> = vec![];
+    for item in DEBTS.prefix(driver).range(deps.storage, None, None, Order::Ascending) {
+        let (_, debt) = item?;
+        match totals.iter_mut().find(|total| total.asset == debt.asset) {
+            Some(total) => total.amount = total.amount.checked_add(debt.amount)?,
+            None => totals.push(AssetAmount { asset: debt.asset, amount: debt.amount }),
+        }
+    }
+    Ok(totals)
+}
+
+pub fn has_debt(deps: Deps, driver: &Addr) -> bool {
+    DEBTS
+        .prefix(driver)
+        .keys_raw(deps.storage, None, None, Order::Ascending)
+        .next()
+        .is_some()
+}
+
+pub fn get_energy_transfers(deps: Deps<'_>, start_after: Option<u64>) -> Box<dyn Iterator<Item=StdResult<(u64, EnergyTransfer)>> + '_> {
+    energy_transfers().range(deps.storage, start_after.map(Bound::exclusive), None, Order::Ascending)
+}
+
+pub fn get_energy_transfer_offers(deps: Deps<'_>, start_after: Option<u64>) -> Box<dyn Iterator<Item=StdResult<(u64, EnergyTransferOffer)>> + '_> {
+    energy_transfer_offers().range(deps.storage, start_after.map(Bound::exclusive), None, Order::Ascending)
+}
+
+/// Results are returned in ascending key order, `limit` defaults to
+/// `DEFAULT_LIMIT` and is capped at `MAX_LIMIT`.
+fn page_size(limit: Option<u32>) -> usize {
+    limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize
 }
 
-pub fn get_energy_transfer_offers(deps: Deps<'_>) -> Box<dyn Iterator<Item=StdResult<(u64, EnergyTransferOffer)>> + '_> {
-    return ENERGY_TRANSFER_OFFERS.range(deps.storage, None, None, cosmwasm_std::Order::Ascending);
+/// The given asset, or the native denom when none is given.
+pub fn asset_or_native(deps: Deps, asset: Option<Asset>) -> StdResult<Asset> {
+    match asset {
+        Some(asset) => Ok(asset),
+        None => Ok(Asset::Native(CONFIG.load(deps.storage)?.denom)),
+    }
 }
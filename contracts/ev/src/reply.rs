@@ -0,0 +1,41 @@
+use cosmwasm_std::{DepsMut, Event, Response};
+use crate::ContractError;
+use crate::state::{CLAIMABLE, PAYOUTS};
+
+/// Drops the record of a payout that was delivered.
+pub fn payout_succeeded(deps: DepsMut, reply_id: u64) -> Result<Response, ContractError> {
+    if !PAYOUTS.has(deps.storage, reply_id) {
+        return Err(ContractError::UnknownReplyId(reply_id));
+    }
+    PAYOUTS.remove(deps.storage, reply_id);
+    Ok(Response::new())
+}
+
+/// Credits a payout whose bank send failed to the recipient's claimable balance,
+/// keeping the settlement that dispatched it intact.
+pub fn payout_failed(
+    deps: DepsMut,
+    reply_id: u64,
+    error: String,
+) -> Result<Response, ContractError> {
+    let payout = PAYOUTS
+        .may_load(deps.storage, reply_id)?
+        .ok_or(ContractError::UnknownReplyId(reply_id))?;
+    PAYOUTS.remove(deps.storage, reply_id);
+
+    let recipient = deps.api.addr_validate(&payout.recipient)?;
+    CLAIMABLE.update(deps.storage, (&recipient, &payout.asset.to_string()), |claimable| -> Result<_, ContractError> {
+        Ok(claimable.unwrap_or_default().checked_add(payout.amount)?)
+    })?;
+
+    let events = vec![
+        Event::new("payout_failed")
+            .add_attribute("energy_transfer_id", payout.energy_transfer_id.to_string())
+            .add_attribute("recipient", payout.recipient)
+            .add_attribute("amount", payout.amount.to_string())
+            .add_attribute("asset", payout.asset.to_string())
+            .add_attribute("error", error),
+    ];
+
+    Ok(Response::new().add_events(events))
+}
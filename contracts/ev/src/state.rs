@@ -1,8 +1,102 @@
-use crate::msg::{EnergyTransfer, EnergyTransferOffer};
-use cw_storage_plus::{Map, Item};
+use cosmwasm_std::{Addr, Empty, Uint128};
+use crate::msg::{Config, Connector, ContractStatus, Debt, Deposit, EnergyTransfer, EnergyTransferOffer, Payout};
+use cw_storage_plus::{Index, IndexList, IndexedMap, Item, Map, MultiIndex};
+
+pub struct EnergyTransferOfferIndexes<'a> {
+    pub owner: MultiIndex<'a, String, EnergyTransferOffer, u64>,
+    pub charger_id: MultiIndex<'a, String, EnergyTransferOffer, u64>,
+}
+
+impl<'a> IndexList<EnergyTransferOffer> for EnergyTransferOfferIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<EnergyTransferOffer>> + '_> {
+        let v: Vec<&dyn Index<EnergyTransferOffer>> = vec![&self.owner, &self.charger_id];
+        Box::new(v.into_iter())
+    }
+}
+
+pub fn energy_transfer_offers<'a>() -> IndexedMap<'a, u64, EnergyTransferOffer, EnergyTransferOfferIndexes<'a>> {
+    let indexes = EnergyTransferOfferIndexes {
+        owner: MultiIndex::new(
+            |offer| offer.owner.clone(),
+            "energy_transfer_offers",
+            "energy_transfer_offers__owner",
+        ),
+        charger_id: MultiIndex::new(
+            |offer| offer.charger_id.clone(),
+            "energy_transfer_offers",
+            "energy_transfer_offers__charger_id",
+        ),
+    };
+    IndexedMap::new("energy_transfer_offers", indexes)
+}
+
+/// `driver` is keyed by (driver, status) so a driver's transfers can be listed
+/// as a whole through `sub_prefix` or narrowed down to one status through `prefix`.
+pub struct EnergyTransferIndexes<'a> {
+    pub owner: MultiIndex<'a, String, EnergyTransfer, u64>,
+    pub driver: MultiIndex<'a, (String, String), EnergyTransfer, u64>,
+    pub payer: MultiIndex<'a, String, EnergyTransfer, u64>,
+    pub charger_id: MultiIndex<'a, String, EnergyTransfer, u64>,
+    pub status: MultiIndex<'a, String, EnergyTransfer, u64>,
+}
+
+impl<'a> IndexList<EnergyTransfer> for EnergyTransferIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<EnergyTransfer>> + '_> {
+        let v: Vec<&dyn Index<EnergyTransfer>> = vec![&self.owner, &self.driver, &self.payer, &self.charger_id, &self.status];
+        Box::new(v.into_iter())
+    }
+}
+
+pub fn energy_transfers<'a>() -> IndexedMap<'a, u64, EnergyTransfer, EnergyTransferIndexes<'a>> {
+    let indexes = EnergyTransferIndexes {
+        owner: MultiIndex::new(
+            |transfer| transfer.owner.clone(),
+            "energy_transfers",
+            "energy_transfers__owner",
+        ),
+        driver: MultiIndex::new(
+            |transfer| (transfer.driver.clone(), transfer.status.to_string()),
+            "energy_transfers",
+            "energy_transfers__driver",
+        ),
+        payer: MultiIndex::new(
+            |transfer| transfer.payer.clone(),
+            "energy_transfers",
+            "energy_transfers__payer",
+        ),
+        charger_id: MultiIndex::new(
+            |transfer| transfer.charger_id.clone(),
+            "energy_transfers",
+            "energy_transfers__charger_id",
+        ),
+        status: MultiIndex::new(
+            |transfer| transfer.status.to_string(),
+            "energy_transfers",
+            "energy_transfers__status",
+        ),
+    };
+    IndexedMap::new("energy_transfers", indexes)
+}
 
-pub const ENERGY_TRANSFER_OFFERS: Map<u64, EnergyTransferOffer> = Map::new("energy_transfer_offers");
 pub const ENERGY_TRANSFER_OFFER_COUNT: Item<u64> = Item::new("energy_transfer_offer_count");
-pub const ENERGY_TRANSFERS: Map<u64, EnergyTransfer> = Map::new("energy_transfers");
 pub const ENERGY_TRANSFER_COUNT: Item<u64> = Item::new("energy_transfer_count");
-pub const DENOM: Item<String> = Item::new("denom");
+pub const CONFIG: Item<Config> = Item::new("config");
+pub const PENDING_ADMIN: Item<Addr> = Item::new("pending_admin");
+pub const CONNECTORS: Map<&Addr, Connector> = Map::new("connectors");
+pub const CONTRACT_STATUS: Item<ContractStatus> = Item::new("contract_status");
+/// Payouts dispatched as submessages, keyed by reply id.
+pub const PAYOUTS: Map<u64, Payout> = Map::new("payouts");
+pub const PAYOUT_COUNT: Item<u64> = Item::new("payout_count");
+/// Funds owed to addresses whose payout failed, keyed by address and asset.
+pub const CLAIMABLE: Map<(&Addr, &str), Uint128> = Map::new("claimable");
+/// Owner earnings credited in `PayoutMode::Pull`, collected with `Withdraw`.
+/// Keyed by owner and asset.
+pub const EARNINGS: Map<(&Addr, &str), Uint128> = Map::new("earnings");
+/// Prepaid driver balances in the native denom sessions can be started from.
+pub const DEPOSITS: Map<&Addr, Deposit> = Map::new("deposits");
+/// Connectors a driver allows to start sessions from its deposit, keyed by driver and connector.
+pub const DEPOSIT_CONNECTORS: Map<(&Addr, &Addr), Empty> = Map::new("deposit_connectors");
+/// Protocol fees charged over the lifetime of the contract, keyed by asset.
+pub const FEES_COLLECTED: Map<&str, Uint128> = Map::new("fees_collected");
+/// Overconsumption owed by drivers, keyed by driver and energy transfer id.
+pub const DEBTS: Map<(&Addr, u64), Debt> = Map::new("debts");
@@ -6,7 +6,7 @@
 
 import { CosmWasmClient, SigningCosmWasmClient, ExecuteResult } from "@cosmjs/cosmwasm-stargate";
 import { Coin, StdFee } from "@cosmjs/amino";
-import { InstantiateMsg, ExecuteMsg, PlugType, Location, QueryMsg, TransferStatus, MigrateMsg, ChargerStatus, AllEnergyTransferOffersResponse, EnergyTransferOffer, Timestamp, Uint64, AllEnergyTransfersResponse, EnergyTransfer, DenomResponse, EnergyTransferResponse, EnergyTransferOfferResponse, EnergyTransferOffersByOwnerResponse, EnergyTransfersByOwnerResponse, OwnEnergyTransfersResponse } from "./Sg721.types";
+import { Decimal, PayoutMode, TimeoutPolicy, InstantiateMsg, Limits, ExecuteMsg, OverconsumptionPolicy, PlugType, Asset, Addr, ChargerStatus, Timestamp, Uint64, Uint128, Binary, FiatTariff, Location, Price, Cw20ReceiveMsg, QueryMsg, TransferStatus, MigrateMsg, AllEnergyTransferOffersResponse, EnergyTransferOffer, AllEnergyTransfersResponse, EnergyTransfer, BalanceResponse, EnergyTransferOffersByChargerResponse, EnergyTransfersByChargerResponse, ClaimableResponse, ConfigResponse, Config, ConnectorsResponse, Connector, ContractStatusResponse, ContractStatus, DebtsResponse, Debt, AssetAmount, DenomResponse, DepositResponse, DepositConnectorsResponse, EnergyTransferResponse, EnergyTransferOfferResponse, EnergyTransferOffersByOwnerResponse, EnergyTransferProgressResponse, EnergyTransfersByOwnerResponse, EnergyTransfersByStatusResponse, FeeInfoResponse, OwnEnergyTransfersResponse, EnergyTransfersByPayerResponse } from "./Sg721.types";
 export interface Sg721ReadOnlyInterface {
   contractAddress: string;
   denom: () => Promise<DenomResponse>;
@@ -15,30 +15,146 @@ export interface Sg721ReadOnlyInterface {
   }: {
     id: number;
   }) => Promise<EnergyTransferOfferResponse>;
-  allEnergyTransferOffers: () => Promise<AllEnergyTransferOffersResponse>;
+  allEnergyTransferOffers: ({
+    limit,
+    startAfter
+  }: {
+    limit?: number;
+    startAfter?: number;
+  }) => Promise<AllEnergyTransferOffersResponse>;
   energyTransfer: ({
     id
   }: {
     id: number;
   }) => Promise<EnergyTransferResponse>;
-  allEnergyTransfers: () => Promise<AllEnergyTransfersResponse>;
+  energyTransferProgress: ({
+    id
+  }: {
+    id: number;
+  }) => Promise<EnergyTransferProgressResponse>;
+  allEnergyTransfers: ({
+    limit,
+    startAfter
+  }: {
+    limit?: number;
+    startAfter?: number;
+  }) => Promise<AllEnergyTransfersResponse>;
   energyTransferOffers: ({
-    owner
+    limit,
+    owner,
+    startAfter
   }: {
+    limit?: number;
     owner: string;
+    startAfter?: number;
   }) => Promise<EnergyTransferOffersByOwnerResponse>;
   ownEnergyTransfers: ({
     driver,
+    limit,
+    startAfter,
     transferStatus
   }: {
     driver: string;
+    limit?: number;
+    startAfter?: number;
     transferStatus: TransferStatus;
   }) => Promise<OwnEnergyTransfersResponse>;
   energyTransfers: ({
-    owner
+    limit,
+    owner,
+    startAfter
   }: {
+    limit?: number;
     owner: string;
+    startAfter?: number;
   }) => Promise<EnergyTransfersByOwnerResponse>;
+  payerEnergyTransfers: ({
+    limit,
+    payer,
+    startAfter
+  }: {
+    limit?: number;
+    payer: string;
+    startAfter?: number;
+  }) => Promise<EnergyTransfersByPayerResponse>;
+  chargerEnergyTransferOffers: ({
+    chargerId,
+    limit,
+    startAfter
+  }: {
+    chargerId: string;
+    limit?: number;
+    startAfter?: number;
+  }) => Promise<EnergyTransferOffersByChargerResponse>;
+  chargerEnergyTransfers: ({
+    chargerId,
+    limit,
+    startAfter
+  }: {
+    chargerId: string;
+    limit?: number;
+    startAfter?: number;
+  }) => Promise<EnergyTransfersByChargerResponse>;
+  energyTransfersByStatus: ({
+    limit,
+    startAfter,
+    transferStatus
+  }: {
+    limit?: number;
+    startAfter?: number;
+    transferStatus: TransferStatus;
+  }) => Promise<EnergyTransfersByStatusResponse>;
+  connectors: ({
+    limit,
+    startAfter
+  }: {
+    limit?: number;
+    startAfter?: string;
+  }) => Promise<ConnectorsResponse>;
+  config: () => Promise<ConfigResponse>;
+  contractStatus: () => Promise<ContractStatusResponse>;
+  claimable: ({
+    address,
+    asset
+  }: {
+    address: string;
+    asset?: Asset;
+  }) => Promise<ClaimableResponse>;
+  debts: ({
+    driver,
+    limit,
+    startAfter
+  }: {
+    driver: string;
+    limit?: number;
+    startAfter?: number;
+  }) => Promise<DebtsResponse>;
+  feeInfo: ({
+    asset
+  }: {
+    asset?: Asset;
+  }) => Promise<FeeInfoResponse>;
+  balance: ({
+    address,
+    asset
+  }: {
+    address: string;
+    asset?: Asset;
+  }) => Promise<BalanceResponse>;
+  deposit: ({
+    address
+  }: {
+    address: string;
+  }) => Promise<DepositResponse>;
+  depositConnectors: ({
+    address,
+    limit,
+    startAfter
+  }: {
+    address: string;
+    limit?: number;
+    startAfter?: string;
+  }) => Promise<DepositConnectorsResponse>;
 }
 export class Sg721QueryClient implements Sg721ReadOnlyInterface {
   client: CosmWasmClient;
@@ -51,10 +167,24 @@ export class Sg721QueryClient implements Sg721ReadOnlyInterface {
     this.energyTransferOffer = this.energyTransferOffer.bind(this);
     this.allEnergyTransferOffers = this.allEnergyTransferOffers.bind(this);
     this.energyTransfer = this.energyTransfer.bind(this);
+    this.energyTransferProgress = this.energyTransferProgress.bind(this);
     this.allEnergyTransfers = this.allEnergyTransfers.bind(this);
     this.energyTransferOffers = this.energyTransferOffers.bind(this);
     this.ownEnergyTransfers = this.ownEnergyTransfers.bind(this);
     this.energyTransfers = this.energyTransfers.bind(this);
+    this.payerEnergyTransfers = this.payerEnergyTransfers.bind(this);
+    this.chargerEnergyTransferOffers = this.chargerEnergyTransferOffers.bind(this);
+    this.chargerEnergyTransfers = this.chargerEnergyTransfers.bind(this);
+    this.energyTransfersByStatus = this.energyTransfersByStatus.bind(this);
+    this.connectors = this.connectors.bind(this);
+    this.config = this.config.bind(this);
+    this.contractStatus = this.contractStatus.bind(this);
+    this.claimable = this.claimable.bind(this);
+    this.debts = this.debts.bind(this);
+    this.feeInfo = this.feeInfo.bind(this);
+    this.balance = this.balance.bind(this);
+    this.deposit = this.deposit.bind(this);
+    this.depositConnectors = this.depositConnectors.bind(this);
   }
 
   denom = async (): Promise<DenomResponse> => {
@@ -73,9 +203,18 @@ export class Sg721QueryClient implements Sg721ReadOnlyInterface {
       }
     });
   };
-  allEnergyTransferOffers = async (): Promise<AllEnergyTransferOffersResponse> => {
+  allEnergyTransferOffers = async ({
+    limit,
+    startAfter
+  }: {
+    limit?: number;
+    startAfter?: number;
+  }): Promise<AllEnergyTransferOffersResponse> => {
     return this.client.queryContractSmart(this.contractAddress, {
-      all_energy_transfer_offers: {}
+      all_energy_transfer_offers: {
+        limit,
+        start_after: startAfter
+      }
     });
   };
   energyTransfer = async ({
@@ -89,44 +228,258 @@ export class Sg721QueryClient implements Sg721ReadOnlyInterface {
       }
     });
   };
-  allEnergyTransfers = async (): Promise<AllEnergyTransfersResponse> => {
+  energyTransferProgress = async ({
+    id
+  }: {
+    id: number;
+  }): Promise<EnergyTransferProgressResponse> => {
     return this.client.queryContractSmart(this.contractAddress, {
-      all_energy_transfers: {}
+      energy_transfer_progress: {
+        id
+      }
+    });
+  };
+  allEnergyTransfers = async ({
+    limit,
+    startAfter
+  }: {
+    limit?: number;
+    startAfter?: number;
+  }): Promise<AllEnergyTransfersResponse> => {
+    return this.client.queryContractSmart(this.contractAddress, {
+      all_energy_transfers: {
+        limit,
+        start_after: startAfter
+      }
     });
   };
   energyTransferOffers = async ({
-    owner
+    limit,
+    owner,
+    startAfter
   }: {
+    limit?: number;
     owner: string;
+    startAfter?: number;
   }): Promise<EnergyTransferOffersByOwnerResponse> => {
     return this.client.queryContractSmart(this.contractAddress, {
       energy_transfer_offers: {
-        owner
+        limit,
+        owner,
+        start_after: startAfter
       }
     });
   };
   ownEnergyTransfers = async ({
     driver,
+    limit,
+    startAfter,
     transferStatus
   }: {
     driver: string;
+    limit?: number;
+    startAfter?: number;
     transferStatus: TransferStatus;
   }): Promise<OwnEnergyTransfersResponse> => {
     return this.client.queryContractSmart(this.contractAddress, {
       own_energy_transfers: {
         driver,
+        limit,
+        start_after: startAfter,
         transfer_status: transferStatus
       }
     });
   };
   energyTransfers = async ({
-    owner
+    limit,
+    owner,
+    startAfter
   }: {
+    limit?: number;
     owner: string;
+    startAfter?: number;
   }): Promise<EnergyTransfersByOwnerResponse> => {
     return this.client.queryContractSmart(this.contractAddress, {
       energy_transfers: {
-        owner
+        limit,
+        owner,
+        start_after: startAfter
+      }
+    });
+  };
+  payerEnergyTransfers = async ({
+    limit,
+    payer,
+    startAfter
+  }: {
+    limit?: number;
+    payer: string;
+    startAfter?: number;
+  }): Promise<EnergyTransfersByPayerResponse> => {
+    return this.client.queryContractSmart(this.contractAddress, {
+      payer_energy_transfers: {
+        limit,
+        payer,
+        start_after: startAfter
+      }
+    });
+  };
+  chargerEnergyTransferOffers = async ({
+    chargerId,
+    limit,
+    startAfter
+  }: {
+    chargerId: string;
+    limit?: number;
+    startAfter?: number;
+  }): Promise<EnergyTransferOffersByChargerResponse> => {
+    return this.client.queryContractSmart(this.contractAddress, {
+      charger_energy_transfer_offers: {
+        charger_id: chargerId,
+        limit,
+        start_after: startAfter
+      }
+    });
+  };
+  chargerEnergyTransfers = async ({
+    chargerId,
+    limit,
+    startAfter
+  }: {
+    chargerId: string;
+    limit?: number;
+    startAfter?: number;
+  }): Promise<EnergyTransfersByChargerResponse> => {
+    return this.client.queryContractSmart(this.contractAddress, {
+      charger_energy_transfers: {
+        charger_id: chargerId,
+        limit,
+        start_after: startAfter
+      }
+    });
+  };
+  energyTransfersByStatus = async ({
+    limit,
+    startAfter,
+    transferStatus
+  }: {
+    limit?: number;
+    startAfter?: number;
+    transferStatus: TransferStatus;
+  }): Promise<EnergyTransfersByStatusResponse> => {
+    return this.client.queryContractSmart(this.contractAddress, {
+      energy_transfers_by_status: {
+        limit,
+        start_after: startAfter,
+        transfer_status: transferStatus
+      }
+    });
+  };
+  connectors = async ({
+    limit,
+    startAfter
+  }: {
+    limit?: number;
+    startAfter?: string;
+  }): Promise<ConnectorsResponse> => {
+    return this.client.queryContractSmart(this.contractAddress, {
+      connectors: {
+        limit,
+        start_after: startAfter
+      }
+    });
+  };
+  config = async (): Promise<ConfigResponse> => {
+    return this.client.queryContractSmart(this.contractAddress, {
+      config: {}
+    });
+  };
+  contractStatus = async (): Promise<ContractStatusResponse> => {
+    return this.client.queryContractSmart(this.contractAddress, {
+      contract_status: {}
+    });
+  };
+  claimable = async ({
+    address,
+    asset
+  }: {
+    address: string;
+    asset?: Asset;
+  }): Promise<ClaimableResponse> => {
+    return this.client.queryContractSmart(this.contractAddress, {
+      claimable: {
+        address,
+        asset
+      }
+    });
+  };
+  debts = async ({
+    driver,
+    limit,
+    startAfter
+  }: {
+    driver: string;
+    limit?: number;
+    startAfter?: number;
+  }): Promise<DebtsResponse> => {
+    return this.client.queryContractSmart(this.contractAddress, {
+      debts: {
+        driver,
+        limit,
+        start_after: startAfter
+      }
+    });
+  };
+  feeInfo = async ({
+    asset
+  }: {
+    asset?: Asset;
+  }): Promise<FeeInfoResponse> => {
+    return this.client.queryContractSmart(this.contractAddress, {
+      fee_info: {
+        asset
+      }
+    });
+  };
+  balance = async ({
+    address,
+    asset
+  }: {
+    address: string;
+    asset?: Asset;
+  }): Promise<BalanceResponse> => {
+    return this.client.queryContractSmart(this.contractAddress, {
+      balance: {
+        address,
+        asset
+      }
+    });
+  };
+  deposit = async ({
+    address
+  }: {
+    address: string;
+  }): Promise<DepositResponse> => {
+    return this.client.queryContractSmart(this.contractAddress, {
+      deposit: {
+        address
+      }
+    });
+  };
+  depositConnectors = async ({
+    address,
+    limit,
+    startAfter
+  }: {
+    address: string;
+    limit?: number;
+    startAfter?: string;
+  }): Promise<DepositConnectorsResponse> => {
+    return this.client.queryContractSmart(this.contractAddress, {
+      deposit_connectors: {
+        address,
+        limit,
+        start_after: startAfter
       }
     });
   };
@@ -136,36 +489,87 @@ export interface Sg721Interface extends Sg721ReadOnlyInterface {
   sender: string;
   publishEnergyTransferOffer: ({
     chargerId,
+    fiatTariff,
     location,
+    maxSessionDuration,
     name,
+    overconsumptionPolicy,
     plugType,
+    prices,
     tariff
   }: {
     chargerId: string;
+    fiatTariff?: FiatTariff;
     location: Location;
+    maxSessionDuration?: number;
     name: string;
+    overconsumptionPolicy?: OverconsumptionPolicy;
     plugType: PlugType;
-    tariff: number;
+    prices?: Price[];
+    tariff: Decimal;
   }, fee?: number | StdFee | "auto", memo?: string, _funds?: Coin[]) => Promise<ExecuteResult>;
   removeEnergyOffer: ({
     energyOfferId
   }: {
     energyOfferId: number;
   }, fee?: number | StdFee | "auto", memo?: string, _funds?: Coin[]) => Promise<ExecuteResult>;
+  updateEnergyTransferOffer: ({
+    clearFiatTariff,
+    energyTransferOfferId,
+    fiatTariff,
+    location,
+    maxSessionDuration,
+    name,
+    overconsumptionPolicy,
+    plugType,
+    prices,
+    tariff
+  }: {
+    clearFiatTariff?: boolean;
+    energyTransferOfferId: number;
+    fiatTariff?: FiatTariff;
+    location?: Location;
+    maxSessionDuration?: number;
+    name?: string;
+    overconsumptionPolicy?: OverconsumptionPolicy;
+    plugType?: PlugType;
+    prices?: Price[];
+    tariff?: Decimal;
+  }, fee?: number | StdFee | "auto", memo?: string, _funds?: Coin[]) => Promise<ExecuteResult>;
+  setChargerStatus: ({
+    energyTransferOfferId,
+    status
+  }: {
+    energyTransferOfferId: number;
+    status: ChargerStatus;
+  }, fee?: number | StdFee | "auto", memo?: string, _funds?: Coin[]) => Promise<ExecuteResult>;
   startEnergyTransfer: ({
+    denom,
     driver,
     energyToTransfer,
-    energyTransferOfferId
+    energyTransferOfferId,
+    fromDeposit,
+    maxTariff
   }: {
+    denom?: string;
     driver: string;
     energyToTransfer: number;
     energyTransferOfferId: number;
+    fromDeposit?: boolean;
+    maxTariff?: Decimal;
   }, fee?: number | StdFee | "auto", memo?: string, _funds?: Coin[]) => Promise<ExecuteResult>;
   energyTransferStarted: ({
     energyTransferId
   }: {
     energyTransferId: number;
   }, fee?: number | StdFee | "auto", memo?: string, _funds?: Coin[]) => Promise<ExecuteResult>;
+  extendEnergyTransfer: ({
+    additionalEnergy,
+    energyTransferId
+  }: {
+    additionalEnergy: number;
+    energyTransferId: number;
+  }, fee?: number | StdFee | "auto", memo?: string, _funds?: Coin[]) => Promise<ExecuteResult>;
   energyTransferCompleted: ({
     energyTransferId,
     usedServiceUnits
@@ -173,16 +577,127 @@ export interface Sg721Interface extends Sg721ReadOnlyInterface {
     energyTransferId: number;
     usedServiceUnits: number;
   }, fee?: number | StdFee | "auto", memo?: string, _funds?: Coin[]) => Promise<ExecuteResult>;
+  reportEnergyProgress: ({
+    energyTransferId,
+    energyTransferred,
+    timestamp
+  }: {
+    energyTransferId: number;
+    energyTransferred: number;
+    timestamp: Timestamp;
+  }, fee?: number | StdFee | "auto", memo?: string, _funds?: Coin[]) => Promise<ExecuteResult>;
   cancelEnergyTransfer: ({
     energyTransferId
   }: {
     energyTransferId: number;
   }, fee?: number | StdFee | "auto", memo?: string, _funds?: Coin[]) => Promise<ExecuteResult>;
+  expireEnergyTransfer: ({
+    energyTransferId
+  }: {
+    energyTransferId: number;
+  }, fee?: number | StdFee | "auto", memo?: string, _funds?: Coin[]) => Promise<ExecuteResult>;
+  claimTimedOutEnergyTransfer: ({
+    energyTransferId
+  }: {
+    energyTransferId: number;
+  }, fee?: number | StdFee | "auto", memo?: string, _funds?: Coin[]) => Promise<ExecuteResult>;
   removeEnergyTransfer: ({
     energyTransferId
   }: {
     energyTransferId: number;
   }, fee?: number | StdFee | "auto", memo?: string, _funds?: Coin[]) => Promise<ExecuteResult>;
+  addConnector: ({
+    address,
+    owner
+  }: {
+    address: string;
+    owner?: string;
+  }, fee?: number | StdFee | "auto", memo?: string, _funds?: Coin[]) => Promise<ExecuteResult>;
+  removeConnector: ({
+    address
+  }: {
+    address: string;
+  }, fee?: number | StdFee | "auto", memo?: string, _funds?: Coin[]) => Promise<ExecuteResult>;
+  updateConfig: ({
+    acceptedDenoms,
+    cw20Tokens,
+    feeBps,
+    limits,
+    maxPriceAge,
+    oracle,
+    payoutMode,
+    startTimeout,
+    timeoutPolicy,
+    treasury
+  }: {
+    acceptedDenoms?: string[];
+    cw20Tokens?: string[];
+    feeBps?: number;
+    limits?: Limits;
+    maxPriceAge?: number;
+    oracle?: string;
+    payoutMode?: PayoutMode;
+    startTimeout?: number;
+    timeoutPolicy?: TimeoutPolicy;
+    treasury?: string;
+  }, fee?: number | StdFee | "auto", memo?: string, _funds?: Coin[]) => Promise<ExecuteResult>;
+  proposeAdmin: ({
+    newAdmin
+  }: {
+    newAdmin: string;
+  }, fee?: number | StdFee | "auto", memo?: string, _funds?: Coin[]) => Promise<ExecuteResult>;
+  acceptAdmin: (fee?: number | StdFee | "auto", memo?: string, _funds?: Coin[]) => Promise<ExecuteResult>;
+  setContractStatus: ({
+    paused,
+    payoutsFrozen
+  }: {
+    paused?: boolean;
+    payoutsFrozen?: boolean;
+  }, fee?: number | StdFee | "auto", memo?: string, _funds?: Coin[]) => Promise<ExecuteResult>;
+  claimPayouts: ({
+    asset
+  }: {
+    asset?: Asset;
+  }, fee?: number | StdFee | "auto", memo?: string, _funds?: Coin[]) => Promise<ExecuteResult>;
+  payDebt: ({
+    energyTransferId
+  }: {
+    energyTransferId: number;
+  }, fee?: number | StdFee | "auto", memo?: string, _funds?: Coin[]) => Promise<ExecuteResult>;
+  withdraw: ({
+    amount,
+    asset,
+    recipient
+  }: {
+    amount: Uint128;
+    asset?: Asset;
+    recipient?: string;
+  }, fee?: number | StdFee | "auto", memo?: string, _funds?: Coin[]) => Promise<ExecuteResult>;
+  deposit: (fee?: number | StdFee | "auto", memo?: string, _funds?: Coin[]) => Promise<ExecuteResult>;
+  withdrawDeposit: ({
+    amount
+  }: {
+    amount: Uint128;
+  }, fee?: number | StdFee | "auto", memo?: string, _funds?: Coin[]) => Promise<ExecuteResult>;
+  approveDepositConnector: ({
+    connector
+  }: {
+    connector: string;
+  }, fee?: number | StdFee | "auto", memo?: string, _funds?: Coin[]) => Promise<ExecuteResult>;
+  revokeDepositConnector: ({
+    connector
+  }: {
+    connector: string;
+  }, fee?: number | StdFee | "auto", memo?: string, _funds?: Coin[]) => Promise<ExecuteResult>;
+  receive: ({
+    amount,
+    msg,
+    sender
+  }: {
+    amount: Uint128;
+    msg: Binary;
+    sender: string;
+  }, fee?: number | StdFee | "auto", memo?: string, _funds?: Coin[]) => Promise<ExecuteResult>;
 }
 export class Sg721Client extends Sg721QueryClient implements Sg721Interface {
   client: SigningCosmWasmClient;
@@ -196,32 +711,64 @@ export class Sg721Client extends Sg721QueryClient implements Sg721Interface {
     this.contractAddress = contractAddress;
     this.publishEnergyTransferOffer = this.publishEnergyTransferOffer.bind(this);
     this.removeEnergyOffer = this.removeEnergyOffer.bind(this);
+    this.updateEnergyTransferOffer = this.updateEnergyTransferOffer.bind(this);
+    this.setChargerStatus = this.setChargerStatus.bind(this);
     this.startEnergyTransfer = this.startEnergyTransfer.bind(this);
     this.energyTransferStarted = this.energyTransferStarted.bind(this);
+    this.extendEnergyTransfer = this.extendEnergyTransfer.bind(this);
     this.energyTransferCompleted = this.energyTransferCompleted.bind(this);
+    this.reportEnergyProgress = this.reportEnergyProgress.bind(this);
     this.cancelEnergyTransfer = this.cancelEnergyTransfer.bind(this);
+    this.expireEnergyTransfer = this.expireEnergyTransfer.bind(this);
+    this.claimTimedOutEnergyTransfer = this.claimTimedOutEnergyTransfer.bind(this);
     this.removeEnergyTransfer = this.removeEnergyTransfer.bind(this);
+    this.addConnector = this.addConnector.bind(this);
+    this.removeConnector = this.removeConnector.bind(this);
+    this.updateConfig = this.updateConfig.bind(this);
+    this.proposeAdmin = this.proposeAdmin.bind(this);
+    this.acceptAdmin = this.acceptAdmin.bind(this);
+    this.setContractStatus = this.setContractStatus.bind(this);
+    this.claimPayouts = this.claimPayouts.bind(this);
+    this.payDebt = this.payDebt.bind(this);
+    this.withdraw = this.withdraw.bind(this);
+    this.deposit = this.deposit.bind(this);
+    this.withdrawDeposit = this.withdrawDeposit.bind(this);
+    this.approveDepositConnector = this.approveDepositConnector.bind(this);
+    this.revokeDepositConnector = this.revokeDepositConnector.bind(this);
+    this.receive = this.receive.bind(this);
   }
 
   publishEnergyTransferOffer = async ({
     chargerId,
+    fiatTariff,
     location,
+    maxSessionDuration,
     name,
+    overconsumptionPolicy,
     plugType,
+    prices,
     tariff
   }: {
     chargerId: string;
+    fiatTariff?: FiatTariff;
     location: Location;
+    maxSessionDuration?: number;
     name: string;
+    overconsumptionPolicy?: OverconsumptionPolicy;
     plugType: PlugType;
-    tariff: number;
+    prices?: Price[];
+    tariff: Decimal;
   }, fee: number | StdFee | "auto" = "auto", memo?: string, _funds?: Coin[]): Promise<ExecuteResult> => {
     return await this.client.execute(this.sender, this.contractAddress, {
       publish_energy_transfer_offer: {
         charger_id: chargerId,
+        fiat_tariff: fiatTariff,
         location,
+        max_session_duration: maxSessionDuration,
         name,
+        overconsumption_policy: overconsumptionPolicy,
         plug_type: plugType,
+        prices,
         tariff
       }
     }, fee, memo, _funds);
@@ -237,20 +784,81 @@ export class Sg721Client extends Sg721QueryClient implements Sg721Interface {
       }
     }, fee, memo, _funds);
   };
+  updateEnergyTransferOffer = async ({
+    clearFiatTariff,
+    energyTransferOfferId,
+    fiatTariff,
+    location,
+    maxSessionDuration,
+    name,
+    overconsumptionPolicy,
+    plugType,
+    prices,
+    tariff
+  }: {
+    clearFiatTariff?: boolean;
+    energyTransferOfferId: number;
+    fiatTariff?: FiatTariff;
+    location?: Location;
+    maxSessionDuration?: number;
+    name?: string;
+    overconsumptionPolicy?: OverconsumptionPolicy;
+    plugType?: PlugType;
+    prices?: Price[];
+    tariff?: Decimal;
+  }, fee: number | StdFee | "auto" = "auto", memo?: string, _funds?: Coin[]): Promise<ExecuteResult> => {
+    return await this.client.execute(this.sender, this.contractAddress, {
+      update_energy_transfer_offer: {
+        clear_fiat_tariff: clearFiatTariff,
+        energy_transfer_offer_id: energyTransferOfferId,
+        fiat_tariff: fiatTariff,
+        location,
+        max_session_duration: maxSessionDuration,
+        name,
+        overconsumption_policy: overconsumptionPolicy,
+        plug_type: plugType,
+        prices,
+        tariff
+      }
+    }, fee, memo, _funds);
+  };
+  setChargerStatus = async ({
+    energyTransferOfferId,
+    status
+  }: {
+    energyTransferOfferId: number;
+    status: ChargerStatus;
+  }, fee: number | StdFee | "auto" = "auto", memo?: string, _funds?: Coin[]): Promise<ExecuteResult> => {
+    return await this.client.execute(this.sender, this.contractAddress, {
+      set_charger_status: {
+        energy_transfer_offer_id: energyTransferOfferId,
+        status
+      }
+    }, fee, memo, _funds);
+  };
   startEnergyTransfer = async ({
+    denom,
     driver,
     energyToTransfer,
-    energyTransferOfferId
+    energyTransferOfferId,
+    fromDeposit,
+    maxTariff
   }: {
+    denom?: string;
     driver: string;
     energyToTransfer: number;
     energyTransferOfferId: number;
+    fromDeposit?: boolean;
+    maxTariff?: Decimal;
   }, fee: number | StdFee | "auto" = "auto", memo?: string, _funds?: Coin[]): Promise<ExecuteResult> => {
     return await this.client.execute(this.sender, this.contractAddress, {
       start_energy_transfer: {
+        denom,
         driver,
         energy_to_transfer: energyToTransfer,
-        energy_transfer_offer_id: energyTransferOfferId
+        energy_transfer_offer_id: energyTransferOfferId,
+        from_deposit: fromDeposit,
+        max_tariff: maxTariff
       }
     }, fee, memo, _funds);
   };
@@ -265,6 +873,20 @@ export class Sg721Client extends Sg721QueryClient implements Sg721Interface {
       }
     }, fee, memo, _funds);
   };
+  extendEnergyTransfer = async ({
+    additionalEnergy,
+    energyTransferId
+  }: {
+    additionalEnergy: number;
+    energyTransferId: number;
+  }, fee: number | StdFee | "auto" = "auto", memo?: string, _funds?: Coin[]): Promise<ExecuteResult> => {
+    return await this.client.execute(this.sender, this.contractAddress, {
+      extend_energy_transfer: {
+        additional_energy: additionalEnergy,
+        energy_transfer_id: energyTransferId
+      }
+    }, fee, memo, _funds);
+  };
   energyTransferCompleted = async ({
     energyTransferId,
     usedServiceUnits
@@ -279,6 +901,23 @@ export class Sg721Client extends Sg721QueryClient implements Sg721Interface {
       }
     }, fee, memo, _funds);
   };
+  reportEnergyProgress = async ({
+    energyTransferId,
+    energyTransferred,
+    timestamp
+  }: {
+    energyTransferId: number;
+    energyTransferred: number;
+    timestamp: Timestamp;
+  }, fee: number | StdFee | "auto" = "auto", memo?: string, _funds?: Coin[]): Promise<ExecuteResult> => {
+    return await this.client.execute(this.sender, this.contractAddress, {
+      report_energy_progress: {
+        energy_transfer_id: energyTransferId,
+        energy_transferred: energyTransferred,
+        timestamp
+      }
+    }, fee, memo, _funds);
+  };
   cancelEnergyTransfer = async ({
     energyTransferId
   }: {
@@ -290,6 +929,28 @@ export class Sg721Client extends Sg721QueryClient implements Sg721Interface {
       }
     }, fee, memo, _funds);
   };
+  expireEnergyTransfer = async ({
+    energyTransferId
+  }: {
+    energyTransferId: number;
+  }, fee: number | StdFee | "auto" = "auto", memo?: string, _funds?: Coin[]): Promise<ExecuteResult> => {
+    return await this.client.execute(this.sender, this.contractAddress, {
+      expire_energy_transfer: {
+        energy_transfer_id: energyTransferId
+      }
+    }, fee, memo, _funds);
+  };
+  claimTimedOutEnergyTransfer = async ({
+    energyTransferId
+  }: {
+    energyTransferId: number;
+  }, fee: number | StdFee | "auto" = "auto", memo?: string, _funds?: Coin[]): Promise<ExecuteResult> => {
+    return await this.client.execute(this.sender, this.contractAddress, {
+      claim_timed_out_energy_transfer: {
+        energy_transfer_id: energyTransferId
+      }
+    }, fee, memo, _funds);
+  };
   removeEnergyTransfer = async ({
     energyTransferId
   }: {
@@ -301,4 +962,191 @@ export class Sg721Client extends Sg721QueryClient implements Sg721Interface {
       }
     }, fee, memo, _funds);
   };
+  addConnector = async ({
+    address,
+    owner
+  }: {
+    address: string;
+    owner?: string;
+  }, fee: number | StdFee | "auto" = "auto", memo?: string, _funds?: Coin[]): Promise<ExecuteResult> => {
+    return await this.client.execute(this.sender, this.contractAddress, {
+      add_connector: {
+        address,
+        owner
+      }
+    }, fee, memo, _funds);
+  };
+  removeConnector = async ({
+    address
+  }: {
+    address: string;
+  }, fee: number | StdFee | "auto" = "auto", memo?: string, _funds?: Coin[]): Promise<ExecuteResult> => {
+    return await this.client.execute(this.sender, this.contractAddress, {
+      remove_connector: {
+        address
+      }
+    }, fee, memo, _funds);
+  };
+  updateConfig = async ({
+    acceptedDenoms,
+    cw20Tokens,
+    feeBps,
+    limits,
+    maxPriceAge,
+    oracle,
+    payoutMode,
+    startTimeout,
+    timeoutPolicy,
+    treasury
+  }: {
+    acceptedDenoms?: string[];
+    cw20Tokens?: string[];
+    feeBps?: number;
+    limits?: Limits;
+    maxPriceAge?: number;
+    oracle?: string;
+    payoutMode?: PayoutMode;
+    startTimeout?: number;
+    timeoutPolicy?: TimeoutPolicy;
+    treasury?: string;
+  }, fee: number | StdFee | "auto" = "auto", memo?: string, _funds?: Coin[]): Promise<ExecuteResult> => {
+    return await this.client.execute(this.sender, this.contractAddress, {
+      update_config: {
+        accepted_denoms: acceptedDenoms,
+        cw20_tokens: cw20Tokens,
+        fee_bps: feeBps,
+        limits,
+        max_price_age: maxPriceAge,
+        oracle,
+        payout_mode: payoutMode,
+        start_timeout: startTimeout,
+        timeout_policy: timeoutPolicy,
+        treasury
+      }
+    }, fee, memo, _funds);
+  };
+  proposeAdmin = async ({
+    newAdmin
+  }: {
+    newAdmin: string;
+  }, fee: number | StdFee | "auto" = "auto", memo?: string, _funds?: Coin[]): Promise<ExecuteResult> => {
+    return await this.client.execute(this.sender, this.contractAddress, {
+      propose_admin: {
+        new_admin: newAdmin
+      }
+    }, fee, memo, _funds);
+  };
+  acceptAdmin = async (fee: number | StdFee | "auto" = "auto", memo?: string, _funds?: Coin[]): Promise<ExecuteResult> => {
+    return await this.client.execute(this.sender, this.contractAddress, {
+      accept_admin: {}
+    }, fee, memo, _funds);
+  };
+  setContractStatus = async ({
+    paused,
+    payoutsFrozen
+  }: {
+    paused?: boolean;
+    payoutsFrozen?: boolean;
+  }, fee: number | StdFee | "auto" = "auto", memo?: string, _funds?: Coin[]): Promise<ExecuteResult> => {
+    return await this.client.execute(this.sender, this.contractAddress, {
+      set_contract_status: {
+        paused,
+        payouts_frozen: payoutsFrozen
+      }
+    }, fee, memo, _funds);
+  };
+  claimPayouts = async ({
+    asset
+  }: {
+    asset?: Asset;
+  }, fee: number | StdFee | "auto" = "auto", memo?: string, _funds?: Coin[]): Promise<ExecuteResult> => {
+    return await this.client.execute(this.sender, this.contractAddress, {
+      claim_payouts: {
+        asset
+      }
+    }, fee, memo, _funds);
+  };
+  payDebt = async ({
+    energyTransferId
+  }: {
+    energyTransferId: number;
+  }, fee: number | StdFee | "auto" = "auto", memo?: string, _funds?: Coin[]): Promise<ExecuteResult> => {
+    return await this.client.execute(this.sender, this.contractAddress, {
+      pay_debt: {
+        energy_transfer_id: energyTransferId
+      }
+    }, fee, memo, _funds);
+  };
+  withdraw = async ({
+    amount,
+    asset,
+    recipient
+  }: {
+    amount: Uint128;
+    asset?: Asset;
+    recipient?: string;
+  }, fee: number | StdFee | "auto" = "auto", memo?: string, _funds?: Coin[]): Promise<ExecuteResult> => {
+    return await this.client.execute(this.sender, this.contractAddress, {
+      withdraw: {
+        amount,
+        asset,
+        recipient
+      }
+    }, fee, memo, _funds);
+  };
+  deposit = async (fee: number | StdFee | "auto" = "auto", memo?: string, _funds?: Coin[]): Promise<ExecuteResult> => {
+    return await this.client.execute(this.sender, this.contractAddress, {
+      deposit: {}
+    }, fee, memo, _funds);
+  };
+  withdrawDeposit = async ({
+    amount
+  }: {
+    amount: Uint128;
+  }, fee: number | StdFee | "auto" = "auto", memo?: string, _funds?: Coin[]): Promise<ExecuteResult> => {
+    return await this.client.execute(this.sender, this.contractAddress, {
+      withdraw_deposit: {
+        amount
+      }
+    }, fee, memo, _funds);
+  };
+  approveDepositConnector = async ({
+    connector
+  }: {
+    connector: string;
+  }, fee: number | StdFee | "auto" = "auto", memo?: string, _funds?: Coin[]): Promise<ExecuteResult> => {
+    return await this.client.execute(this.sender, this.contractAddress, {
+      approve_deposit_connector: {
+        connector
+      }
+    }, fee, memo, _funds);
+  };
+  revokeDepositConnector = async ({
+    connector
+  }: {
+    connector: string;
+  }, fee: number | StdFee | "auto" = "auto", memo?: string, _funds?: Coin[]): Promise<ExecuteResult> => {
+    return await this.client.execute(this.sender, this.contractAddress, {
+      revoke_deposit_connector: {
+        connector
+      }
+    }, fee, memo, _funds);
+  };
+  receive = async ({
+    amount,
+    msg,
+    sender
+  }: {
+    amount: Uint128;
+    msg: Binary;
+    sender: string;
+  }, fee: number | StdFee | "auto" = "auto", memo?: string, _funds?: Coin[]): Promise<ExecuteResult> => {
+    return await this.client.execute(this.sender, this.contractAddress, {
+      receive: {
+        amount,
+        msg,
+        sender
+      }
+    }, fee, memo, _funds);
+  };
 }
\ No newline at end of file
@@ -4,50 +4,201 @@
 * and run the @cosmwasm/ts-codegen generate command to regenerate this file.
 */
 
+export type Decimal = string;
+export type PayoutMode = "push" | "pull";
+export type TimeoutPolicy = "full_refund" | "last_meter_reading";
 export interface InstantiateMsg {
+  accepted_denoms?: string[] | null;
+  admin?: string | null;
+  cw20_tokens?: string[] | null;
   denom: string;
+  fee_bps?: number | null;
+  limits?: Limits | null;
+  max_price_age?: number | null;
+  oracle?: string | null;
+  payout_mode?: PayoutMode | null;
+  start_timeout?: number | null;
+  timeout_policy?: TimeoutPolicy | null;
+  treasury?: string | null;
+}
+export interface Limits {
+  max_energy_to_transfer?: number | null;
+  max_session_duration?: number | null;
+  max_tariff?: Decimal | null;
 }
 export type ExecuteMsg = {
   publish_energy_transfer_offer: {
     charger_id: string;
+    fiat_tariff?: FiatTariff | null;
     location: Location;
+    max_session_duration?: number | null;
     name: string;
+    overconsumption_policy?: OverconsumptionPolicy | null;
     plug_type: PlugType;
-    tariff: number;
+    prices?: Price[] | null;
+    tariff: Decimal;
   };
 } | {
   remove_energy_offer: {
     energy_offer_id: number;
   };
+} | {
+  update_energy_transfer_offer: {
+    clear_fiat_tariff?: boolean | null;
+    energy_transfer_offer_id: number;
+    fiat_tariff?: FiatTariff | null;
+    location?: Location | null;
+    max_session_duration?: number | null;
+    name?: string | null;
+    overconsumption_policy?: OverconsumptionPolicy | null;
+    plug_type?: PlugType | null;
+    prices?: Price[] | null;
+    tariff?: Decimal | null;
+  };
+} | {
+  set_charger_status: {
+    energy_transfer_offer_id: number;
+    status: ChargerStatus;
+  };
 } | {
   start_energy_transfer: {
+    denom?: string | null;
     driver: string;
     energy_to_transfer: number;
     energy_transfer_offer_id: number;
+    from_deposit?: boolean | null;
+    max_tariff?: Decimal | null;
   };
 } | {
   energy_transfer_started: {
     energy_transfer_id: number;
   };
+} | {
+  extend_energy_transfer: {
+    additional_energy: number;
+    energy_transfer_id: number;
+  };
 } | {
   energy_transfer_completed: {
     energy_transfer_id: number;
     used_service_units: number;
   };
+} | {
+  report_energy_progress: {
+    energy_transfer_id: number;
+    energy_transferred: number;
+    timestamp: Timestamp;
+  };
 } | {
   cancel_energy_transfer: {
     energy_transfer_id: number;
   };
+} | {
+  expire_energy_transfer: {
+    energy_transfer_id: number;
+  };
+} | {
+  claim_timed_out_energy_transfer: {
+    energy_transfer_id: number;
+  };
 } | {
   remove_energy_transfer: {
     energy_transfer_id: number;
   };
+} | {
+  add_connector: {
+    address: string;
+    owner?: string | null;
+  };
+} | {
+  remove_connector: {
+    address: string;
+  };
+} | {
+  update_config: {
+    accepted_denoms?: string[] | null;
+    cw20_tokens?: string[] | null;
+    fee_bps?: number | null;
+    limits?: Limits | null;
+    max_price_age?: number | null;
+    oracle?: string | null;
+    payout_mode?: PayoutMode | null;
+    start_timeout?: number | null;
+    timeout_policy?: TimeoutPolicy | null;
+    treasury?: string | null;
+  };
+} | {
+  propose_admin: {
+    new_admin: string;
+  };
+} | {
+  accept_admin: {};
+} | {
+  set_contract_status: {
+    paused?: boolean | null;
+    payouts_frozen?: boolean | null;
+  };
+} | {
+  claim_payouts: {
+    asset?: Asset | null;
+  };
+} | {
+  pay_debt: {
+    energy_transfer_id: number;
+  };
+} | {
+  withdraw: {
+    amount: Uint128;
+    asset?: Asset | null;
+    recipient?: string | null;
+  };
+} | {
+  deposit: {};
+} | {
+  withdraw_deposit: {
+    amount: Uint128;
+  };
+} | {
+  approve_deposit_connector: {
+    connector: string;
+  };
+} | {
+  revoke_deposit_connector: {
+    connector: string;
+  };
+} | {
+  receive: Cw20ReceiveMsg;
 };
+export type OverconsumptionPolicy = "hard_cap" | "driver_debt";
 export type PlugType = "type1" | "type2" | "c_h_ade_m_o" | "c_c_s" | "unspecified";
+export type Asset = {
+  native: string;
+} | {
+  cw20: Addr;
+};
+export type Addr = string;
+export type ChargerStatus = "Active" | "Busy" | "Inactive" | "Maintenance" | "Faulted" | "Unspecified";
+export type Timestamp = Uint64;
+export type Uint64 = string;
+export type Uint128 = string;
+export type Binary = string;
+export interface FiatTariff {
+  amount: Decimal;
+  currency: string;
+}
 export interface Location {
   latitude: string;
   longitude: string;
 }
+export interface Price {
+  asset: Asset;
+  tariff: Decimal;
+}
+export interface Cw20ReceiveMsg {
+  amount: Uint128;
+  msg: Binary;
+  sender: string;
+}
 export type QueryMsg = {
   denom: {};
 } | {
@@ -55,64 +206,224 @@ export type QueryMsg = {
     id: number;
   };
 } | {
-  all_energy_transfer_offers: {};
+  all_energy_transfer_offers: {
+    limit?: number | null;
+    start_after?: number | null;
+  };
 } | {
   energy_transfer: {
     id: number;
   };
 } | {
-  all_energy_transfers: {};
+  energy_transfer_progress: {
+    id: number;
+  };
+} | {
+  all_energy_transfers: {
+    limit?: number | null;
+    start_after?: number | null;
+  };
 } | {
   energy_transfer_offers: {
+    limit?: number | null;
     owner: string;
+    start_after?: number | null;
   };
 } | {
   own_energy_transfers: {
     driver: string;
+    limit?: number | null;
+    start_after?: number | null;
     transfer_status: TransferStatus;
   };
 } | {
   energy_transfers: {
+    limit?: number | null;
     owner: string;
+    start_after?: number | null;
+  };
+} | {
+  payer_energy_transfers: {
+    limit?: number | null;
+    payer: string;
+    start_after?: number | null;
+  };
+} | {
+  charger_energy_transfer_offers: {
+    charger_id: string;
+    limit?: number | null;
+    start_after?: number | null;
+  };
+} | {
+  charger_energy_transfers: {
+    charger_id: string;
+    limit?: number | null;
+    start_after?: number | null;
+  };
+} | {
+  energy_transfers_by_status: {
+    limit?: number | null;
+    start_after?: number | null;
+    transfer_status: TransferStatus;
+  };
+} | {
+  connectors: {
+    limit?: number | null;
+    start_after?: string | null;
+  };
+} | {
+  config: {};
+} | {
+  contract_status: {};
+} | {
+  claimable: {
+    address: string;
+    asset?: Asset | null;
+  };
+} | {
+  debts: {
+    driver: string;
+    limit?: number | null;
+    start_after?: number | null;
+  };
+} | {
+  fee_info: {
+    asset?: Asset | null;
+  };
+} | {
+  balance: {
+    address: string;
+    asset?: Asset | null;
+  };
+} | {
+  deposit: {
+    address: string;
+  };
+} | {
+  deposit_connectors: {
+    address: string;
+    limit?: number | null;
+    start_after?: string | null;
   };
 };
-export type TransferStatus = "Requested" | "Ongoing" | "Paid" | "Cancelled" | "Unspecified";
-export type MigrateMsg = string;
-export type ChargerStatus = "Active" | "Busy" | "Inactive" | "Unspecified";
+export type TransferStatus = "Requested" | "Ongoing" | "Paid" | "Cancelled" | "Expired" | "TimedOut" | "Unspecified";
+export interface MigrateMsg {
+  admin?: string | null;
+}
 export interface AllEnergyTransferOffersResponse {
   energy_transfer_offers: EnergyTransferOffer[];
 }
 export interface EnergyTransferOffer {
   charger_id: string;
   charger_status: ChargerStatus;
+  current_energy_transfer_id?: number | null;
+  fiat_tariff?: FiatTariff | null;
   id: number;
   location: Location;
+  max_session_duration: number;
   name: string;
+  overconsumption_policy: OverconsumptionPolicy;
   owner: string;
   plug_type: PlugType;
-  tariff: number;
+  prices: Price[];
+  revision: number;
+  tariff: Decimal;
 }
-export type Timestamp = Uint64;
-export type Uint64 = string;
 export interface AllEnergyTransfersResponse {
   energy_transfers: EnergyTransfer[];
 }
 export interface EnergyTransfer {
+  asset: Asset;
   charger_id: string;
-  collateral: number;
+  collateral: Uint128;
   driver: string;
   energy_to_transfer: number;
   energy_transfer_offer_id: number;
   energy_transferred: number;
+  excess_energy: number;
+  fee_amount: Uint128;
+  from_deposit: boolean;
   id: number;
-  offered_tariff: number;
+  last_reported_at?: Timestamp | null;
+  max_session_duration: number;
+  offered_tariff: Decimal;
+  overconsumption_policy: OverconsumptionPolicy;
   owner: string;
   paid_date: Timestamp;
+  payer: string;
+  start_deadline: Timestamp;
+  started_at?: Timestamp | null;
   status: TransferStatus;
 }
+export interface BalanceResponse {
+  amount: Uint128;
+}
+export interface EnergyTransferOffersByChargerResponse {
+  energy_transfer_offers: EnergyTransferOffer[];
+}
+export interface EnergyTransfersByChargerResponse {
+  energy_transfers: EnergyTransfer[];
+}
+export interface ClaimableResponse {
+  amount: Uint128;
+}
+export interface ConfigResponse {
+  config: Config;
+  pending_admin?: Addr | null;
+}
+export interface Config {
+  accepted_denoms: string[];
+  admin: Addr;
+  cw20_tokens: Addr[];
+  denom: string;
+  fee_bps: number;
+  limits: Limits;
+  max_price_age: number;
+  oracle?: Addr | null;
+  payout_mode: PayoutMode;
+  start_timeout: number;
+  timeout_policy: TimeoutPolicy;
+  treasury?: Addr | null;
+}
+export interface ConnectorsResponse {
+  connectors: Connector[];
+}
+export interface Connector {
+  address: string;
+  owner?: string | null;
+}
+export interface ContractStatusResponse {
+  status: ContractStatus;
+}
+export interface ContractStatus {
+  paused: boolean;
+  payouts_frozen: boolean;
+}
+export interface DebtsResponse {
+  debts: Debt[];
+  total: AssetAmount[];
+}
+export interface Debt {
+  amount: Uint128;
+  asset: Asset;
+  driver: string;
+  energy_transfer_id: number;
+  owner: string;
+}
+export interface AssetAmount {
+  amount: Uint128;
+  asset: Asset;
+}
 export interface DenomResponse {
   denom: string;
 }
+export interface DepositResponse {
+  free: Uint128;
+  locked: Uint128;
+}
+export interface DepositConnectorsResponse {
+  connectors: Addr[];
+}
 export interface EnergyTransferResponse {
   energy_transfer: EnergyTransfer;
 }
@@ -122,9 +433,30 @@ export interface EnergyTransferOfferResponse {
 export interface EnergyTransferOffersByOwnerResponse {
   energy_transfer_offers: EnergyTransferOffer[];
 }
+export interface EnergyTransferProgressResponse {
+  amount_due: Uint128;
+  energy_to_transfer: number;
+  energy_transfer_id: number;
+  energy_transferred: number;
+  last_reported_at?: Timestamp | null;
+  started_at?: Timestamp | null;
+  status: TransferStatus;
+}
 export interface EnergyTransfersByOwnerResponse {
   energy_transfers: EnergyTransfer[];
 }
+export interface EnergyTransfersByStatusResponse {
+  energy_transfers: EnergyTransfer[];
+}
+export interface FeeInfoResponse {
+  fee_bps: number;
+  max_fee_bps: number;
+  total_collected: Uint128;
+  treasury?: Addr | null;
+}
 export interface OwnEnergyTransfersResponse {
   energy_transfers: EnergyTransfer[];
+}
+export interface EnergyTransfersByPayerResponse {
+  energy_transfers: EnergyTransfer[];
 }
\ No newline at end of file